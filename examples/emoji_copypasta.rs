@@ -1,10 +1,12 @@
+use std::time::Duration;
+
 fn main() {
     println!("Typing a bunch of stuff in 5 seconds!");
-    std::thread::sleep_ms(5000);
+    std::thread::sleep(Duration::from_millis(5000));
 
     winput_stuffer::send::send_text("my top genre🎶 is pop 🎤???? POP ??? 😡😤 i am FUCKING fuming. i listen to indie 😎 and indie only 😔 WHY IS MY TOP GENRE POP 😩 spotify you are really 😡 really 😡 really 😡 pissing me off 🙄 I LISTEN 👂TO STRICTLY INDIE MUSIC 👍😤 I BLEED AND BREATHE 👄 INDIE MUSIC😎 NO POP😡").unwrap();
 
-    std::thread::sleep_ms(100);
+    std::thread::sleep(Duration::from_millis(100));
     println!();
     println!();
-}
\ No newline at end of file
+}
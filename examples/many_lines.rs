@@ -1,9 +1,13 @@
-use winput_stuffer::send::send_text;
+use winput_stuffer::send::{send_text, send_text_with_options, TextOptions, NewlinePolicy};
 
 fn main() -> std::io::Result<()> {
     send_text("nline 1\nnline 2\nnline 3\n\n")?;
     send_text("rline 1\rrline 2\rrline 3\r\r")?;
     send_text("rnline 1\r\nrnline 2\r\nrnline 3\r\n\r\n")?;
 
+    // Line breaks that don't send the message in chat apps
+    let soft = TextOptions{newline: NewlinePolicy::ShiftEnter, ..Default::default()};
+    send_text_with_options("soft line 1\nsoft line 2\nsoft line 3", &soft)?;
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,37 +1,42 @@
-use winput_stuffer::input::{
-    Input,
-    KeyboardInput,
-    KeyboardInputEnum,
-    KeyboardInputSys,
-    send_input,
-};
-
+#[cfg(windows)]
 fn main() {
-    let kb_events:Vec<KeyboardInputSys> = "abc123".chars().into_iter().flat_map(|c| {
+    use std::time::Duration;
+
+    use winput_stuffer::input::{
+        Input,
+        KeyboardInput,
+        KeyboardInputEnum,
+        send_input,
+    };
+
+    let events:Vec<Input> = "abc123".chars().flat_map(|c| {
         [
             KeyboardInput{
                 e: KeyboardInputEnum::UnicodeCodeUnit((c as u32).try_into().unwrap()),
                 key_up: false,
                 msg: None,
                 time: None,
-            }.into(),
+            },
             KeyboardInput{
                 e: KeyboardInputEnum::UnicodeCodeUnit((c as u32).try_into().unwrap()),
                 key_up: true,
                 msg: None,
                 time: None,
-            }.into(),
+            },
         ].into_iter()
-    }).collect();
-
-    let events:Vec<Input> = kb_events.into_iter().map(|kb| Input::from_keyboard(&kb)).collect();
+    }).map(|kb| Input::from_keyboard(&kb)).collect();
 
     println!("Typing some stuff in 5 seconds!");
-    std::thread::sleep_ms(5000);
+    std::thread::sleep(Duration::from_millis(5000));
 
     send_input(events.as_slice()).unwrap();
 
-    std::thread::sleep_ms(100);
+    std::thread::sleep(Duration::from_millis(100));
     println!();
     println!();
-}
\ No newline at end of file
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example calls SendInput directly, so it only works on windows");
+}
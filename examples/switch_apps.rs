@@ -1,16 +1,28 @@
-use std::thread::sleep_ms;
+use winput_stuffer::KeyboardLayout;
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::pace::SystemClock;
+use winput_stuffer::script::Script;
+use winput_stuffer::send::TextOptions;
+use winput_stuffer::sink::default_sink;
 
-use winput_stuffer::send::send_key;
+/// Holds Alt and presses Tab twice, to switch to the window before last. Pass `--dry-run` to print what would be sent instead.
+const SWITCH_APPS:&str = "
+down alt_l
+repeat 2 {
+    key tab
+    sleep 200ms
+}
+up alt_l
+";
 
-fn main() -> std::io::Result<()> {
-    send_key("alt_l", true)?;
-    send_key("tab", true)?;
-    send_key("tab", false)?;
-    sleep_ms(200);
-    send_key("tab", true)?;
-    send_key("tab", false)?;
-    sleep_ms(200);
-    send_key("alt_l", false)?;
+fn main() -> winput_stuffer::Result<()> {
+    let layout = KeyboardLayout::current();
+    let timeline = Script::parse(SWITCH_APPS, &layout)?.compile(&layout, &KeyState::current(), &TextOptions::default());
+    if std::env::args().any(|arg| arg == "--dry-run") {
+        print!("{}", timeline);
+        return Ok(());
+    }
+    timeline.run(0, &mut default_sink()?, &mut SystemClock::new())?;
 
     Ok(())
-}
\ No newline at end of file
+}
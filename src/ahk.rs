@@ -0,0 +1,218 @@
+//! AutoHotkey's `Send` syntax, eg "^c", "+{Tab 3}" or "{LWin down}r{LWin up}", so that scripts written for it can be run without rewriting them.
+
+use crate::{Error, Result};
+use crate::combo::key_vk;
+use crate::input::{Input, KeyboardInput, KeyboardInputEnum};
+use crate::key_state::KeyStateSource;
+use crate::layout::KeyboardLayout;
+use crate::layout::maps::*;
+use crate::send::{inputs_for_text_with_options, key_alt_numpad, key_event, key_tap, key_unicode, TextOptions};
+
+/// The most times `{Key N}` can press a key, so that a typo in the count can't take all the memory
+pub const MAX_PRESSES:u32 = 10_000;
+
+/// The key in a braced key or after modifier symbols
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum AhkKey {
+    Vk(u8),
+    /// `{scNNN}`, where codes above 0xff are extended keys
+    ScanCode(u16),
+    /// A character typed with whatever keys the layout types it with
+    Char(char),
+    /// `{U+NNNN}`, always sent as unicode
+    Unicode(char),
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum AhkAction {
+    /// Pressed and released this many times
+    Press(u32),
+    Down,
+    Up,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum AhkItem {
+    /// Literal text, including everything after `{Raw}` or `{Text}`
+    Text(String),
+    /// The modifiers are held around the key, eg for "^c" or "+{Tab 3}"
+    Key{modifiers: Vec<u8>, key: AhkKey, action: AhkAction},
+    /// `{ASC nnn}`, typed as an Alt+Numpad code
+    AltCode(String),
+}
+
+fn modifier_vk(c: char) -> Option<u8> {
+    match c {
+        '^' => Some(VK_LCONTROL),
+        '+' => Some(VK_LSHIFT),
+        '!' => Some(VK_LMENU),
+        '#' => Some(VK_LWIN),
+        _ => None,
+    }
+}
+
+/// Parses a `Send` string. `{Blind}` is accepted at the start but does nothing, since held modifiers are only let go if `TextOptions` says so. Things this crate can't do, such as the mouse, `{Click}` and `{Key DownR}`, are errors rather than being left out.
+pub fn parse(s: &str) -> Result<Vec<AhkItem>> {
+    let mut items = vec![];
+    let mut text = String::new();
+    let mut modifiers = vec![];
+    let mut modifiers_at = 0;
+    let mut i = if s.get(..7).is_some_and(|start| start.eq_ignore_ascii_case("{blind}")) { 7 } else { 0 };
+    let flush = |text: &mut String, items: &mut Vec<AhkItem>| if !text.is_empty() {
+        items.push(AhkItem::Text(std::mem::take(text)));
+    };
+    while let Some(c) = s[i..].chars().next() {
+        if let Some(vk) = modifier_vk(c) {
+            if modifiers.is_empty() {
+                modifiers_at = i;
+            }
+            if !modifiers.contains(&vk) {
+                modifiers.push(vk);
+            }
+            i += 1;
+            continue;
+        }
+        if c != '{' {
+            if modifiers.is_empty() {
+                text.push(c);
+            } else {
+                flush(&mut text, &mut items);
+                items.push(AhkItem::Key{modifiers: std::mem::take(&mut modifiers), key: AhkKey::Char(c), action: AhkAction::Press(1)});
+            }
+            i += c.len_utf8();
+            continue;
+        }
+
+        // The first character is always part of the name, so "{}}" and "{{}" work
+        let name_at = i + 1;
+        let first = s[name_at..].chars().next().ok_or_else(|| Error::syntax_at(s, i, "\"{\" without a closing \"}\""))?;
+        let close = s[name_at + first.len_utf8()..].find('}').ok_or_else(|| Error::syntax_at(s, i, "\"{\" without a closing \"}\""))?
+            + name_at + first.len_utf8();
+        let inner = &s[name_at..close];
+        let name_end = inner[first.len_utf8()..].find(char::is_whitespace).map_or(inner.len(), |e| e + first.len_utf8());
+        let (name, arg) = (&inner[..name_end], inner[name_end..].trim());
+        let arg_at = close - inner[name_end..].trim_start().len();
+        let lower = name.to_lowercase();
+        let unsupported = |what: &str| Err(Error::syntax_at(s, i, format!("{} isn't supported", what)));
+
+        match lower.as_str() {
+            "raw" | "text" if arg.is_empty() => {
+                if !modifiers.is_empty() {
+                    return Err(Error::syntax_at(s, modifiers_at, format!("modifiers before {{{}}} have nothing to apply to", name)));
+                }
+                text.push_str(&s[close + 1..]);
+                break;
+            },
+            "blind" => return Err(Error::syntax_at(s, i, "{Blind} has to be at the start")),
+            "click" | "lbutton" | "rbutton" | "mbutton" | "xbutton1" | "xbutton2" | "wheelup" | "wheeldown" | "wheelleft" | "wheelright" => {
+                return unsupported(&format!("the mouse ({{{}}})", name));
+            },
+            "asc" => {
+                if !modifiers.is_empty() {
+                    return Err(Error::syntax_at(s, modifiers_at, "modifiers can't be held for {ASC}"));
+                }
+                if arg.is_empty() || !arg.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(Error::syntax_at(s, i, format!("{{ASC}} needs a decimal code, not {:?}", arg)));
+                }
+                flush(&mut text, &mut items);
+                items.push(AhkItem::AltCode(arg.into()));
+            },
+            _ => {
+                let key = parse_key(name, &lower).ok_or_else(|| Error::syntax_at(s, name_at, format!("unknown key name {:?}", name)))?;
+                let action = match arg.to_lowercase().as_str() {
+                    "" => AhkAction::Press(1),
+                    "down" => AhkAction::Down,
+                    "up" => AhkAction::Up,
+                    "downr" | "downtemp" => return unsupported(&format!("{{{} {}}}", name, arg)),
+                    count => match count.parse() {
+                        Ok(n) if n <= MAX_PRESSES => AhkAction::Press(n),
+                        Ok(_) => return Err(Error::syntax_at(s, arg_at, format!("a key can be pressed at most {} times at once", MAX_PRESSES))),
+                        Err(_) => return Err(Error::syntax_at(s, i, format!("expected a repeat count, down or up after {:?}, not {:?}", name, arg))),
+                    },
+                };
+                if matches!(key, AhkKey::Unicode(_)) && !matches!(action, AhkAction::Press(_)) {
+                    return unsupported(&format!("{{{} {}}}", name, arg));
+                }
+                flush(&mut text, &mut items);
+                items.push(AhkItem::Key{modifiers: std::mem::take(&mut modifiers), key, action});
+            },
+        }
+        i = close + 1;
+    }
+    if !modifiers.is_empty() {
+        return Err(Error::syntax_at(s, modifiers_at, "modifiers at the end have nothing to apply to"));
+    }
+    flush(&mut text, &mut items);
+    Ok(items)
+}
+
+/// The key for a braced name other than the special ones
+fn parse_key(name: &str, lower: &str) -> Option<AhkKey> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(AhkKey::Char(c));
+    }
+    if let Some(hex) = lower.strip_prefix("u+") {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(AhkKey::Unicode);
+    }
+    // "{vkNNscNNN}" names both, but only one can be sent, and the virtual key is what programs nearly always look at
+    if let Some(rest) = lower.strip_prefix("vk") {
+        let hex = rest.split_once("sc").map_or(rest, |(vk, _)| vk);
+        return u8::from_str_radix(hex, 16).ok().filter(|vk| (1..=254).contains(vk)).map(AhkKey::Vk);
+    }
+    if let Some(hex) = lower.strip_prefix("sc") {
+        return u16::from_str_radix(hex, 16).ok().filter(|sc| (1..=0x1ff).contains(sc)).map(AhkKey::ScanCode);
+    }
+    let upper = name.to_uppercase();
+    let vk_name = AHK_KEY_NAME_TO_VIRTUAL_KEY.get(upper.as_str()).copied().unwrap_or(&upper);
+    VIRTUAL_KEY_TO_CODE.get_by_left(vk_name).copied().map(AhkKey::Vk)
+}
+
+fn scan_code_event(sc: u16, key_down: bool, options: &TextOptions) -> Input {
+    Input::from_keyboard(&KeyboardInput{
+        e: KeyboardInputEnum::ScanCode{code: sc & 0xff, extended: sc > 0xff},
+        key_up: !key_down,
+        msg: options.msg,
+        time: None,
+    })
+}
+
+/// Plans parsed `Send` items for a keyboard in the given state. Text is typed as `inputs_for_text_with_options` would.
+pub fn inputs_for_ahk<K: KeyStateSource>(
+    items: &[AhkItem],
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    out: &mut Vec<Input>,
+) -> Result<()> {
+    let msg = options.msg;
+    for item in items {
+        match item {
+            AhkItem::Text(text) => inputs_for_text_with_options(text, layout, state, options, out),
+            AhkItem::AltCode(digits) => out.extend(key_alt_numpad(digits, false, state.is_toggled(VK_NUMLOCK), msg)),
+            AhkItem::Key{modifiers, key, action} => {
+                out.extend(modifiers.iter().map(|vk| Input::from_keyboard(&key_event(*vk, true, msg))));
+                match (*key, *action) {
+                    (AhkKey::Vk(vk), AhkAction::Press(n)) => (0..n).for_each(|_| out.extend(key_tap(vk, msg))),
+                    (AhkKey::ScanCode(sc), AhkAction::Press(n)) => {
+                        (0..n).for_each(|_| out.extend([true, false].map(|down| scan_code_event(sc, down, options))));
+                    },
+                    (AhkKey::Char(c), AhkAction::Press(n)) => {
+                        let text:String = std::iter::repeat_n(c, n as usize).collect();
+                        inputs_for_text_with_options(&text, layout, state, options, out);
+                    },
+                    (AhkKey::Unicode(c), AhkAction::Press(n)) => (0..n).for_each(|_| out.extend(key_unicode(c, msg))),
+                    (AhkKey::Vk(vk), action) => out.push(Input::from_keyboard(&key_event(vk, action == AhkAction::Down, msg))),
+                    (AhkKey::ScanCode(sc), action) => out.push(scan_code_event(sc, action == AhkAction::Down, options)),
+                    (AhkKey::Char(c), action) => {
+                        let vk = key_vk(&c.to_string(), layout)?;
+                        out.push(Input::from_keyboard(&key_event(vk, action == AhkAction::Down, msg)));
+                    },
+                    (AhkKey::Unicode(c), _) => return Err(Error::InvalidData(format!("U+{:04X} can only be pressed, not held", u32::from(c)))),
+                }
+                out.extend(modifiers.iter().rev().map(|vk| Input::from_keyboard(&key_event(*vk, false, msg))));
+            },
+        }
+    }
+    Ok(())
+}
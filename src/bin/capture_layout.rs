@@ -0,0 +1,17 @@
+//! Dumps the keyboard layout of the foreground window, so it can be loaded with `KeyboardLayout::load` somewhere else.
+//!
+//! Usage: `capture-layout [PATH]`. The file is written as TOML if PATH ends in `.toml` and JSON otherwise. Without a PATH the JSON is printed to stdout.
+
+use winput_stuffer::KeyboardLayout;
+
+fn main() -> std::io::Result<()> {
+    let layout = KeyboardLayout::current();
+    match std::env::args_os().nth(1) {
+        Some(path) => {
+            layout.save(&path)?;
+            eprintln!("saved layout {:08x} to {}", layout.id().0, std::path::Path::new(&path).display());
+        },
+        None => println!("{}", layout.to_json()),
+    }
+    Ok(())
+}
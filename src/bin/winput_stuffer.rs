@@ -0,0 +1,256 @@
+//! Sends keyboard and mouse input from the command line, for scripts that aren't written in Rust.
+//!
+//! Run `winput-stuffer --help` for the usage.
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use winput_stuffer::KeyboardLayout;
+use winput_stuffer::clipboard::default_clipboard;
+use winput_stuffer::combo::{key_vk, Combo};
+use winput_stuffer::input::{Input, MouseButton};
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::pace::{chunks, Pacing, SystemClock};
+use winput_stuffer::script::{parse_duration, Script, Statement, Step, Timeline};
+use winput_stuffer::send::{inputs_for_text_unicode, paste_text, PasteOptions, TextOptions};
+use winput_stuffer::sink::default_sink;
+
+const USAGE:&str = "\
+Usage: winput-stuffer [OPTIONS] [--] COMMAND
+
+Commands:
+  type TEXT...              Type the text, with the words joined by spaces
+  type --file PATH          Type the contents of a file, or of stdin if PATH is -
+  key NAME [--down|--up]    Press and release a key, or only press or release it
+  combo COMBO               Press a combo like ctrl+alt+t
+  mouse move DX DY          Move the mouse by DX, DY pixels
+  mouse click [BUTTON]      Click left (the default), right, middle, x1 or x2
+  mouse scroll CLICKS       Turn the wheel, positive going away from the user
+  layout dump               List the keys that type each character
+
+Options, which go before the command:
+  --delay DURATION          Wait this long before starting, eg 3s or 500ms
+  --strategy vk|unicode|paste
+                            Type text with the layout's keys (the default), as unicode, or by pasting it
+  --rate CHARS_PER_SECOND   Type text at this speed instead of all at once
+  --chunk STROKES           How many keystrokes to send at a time with --rate (default 1)
+  --layout KLID             Plan with a bundled layout, eg 00000407, instead of the current one
+  --dry-run                 Print the inputs instead of sending them
+  -h, --help                Show this help
+";
+
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+enum Strategy {
+    #[default]
+    Vk,
+    Unicode,
+    Paste,
+}
+
+#[derive(Debug,Default)]
+struct Options {
+    delay: Duration,
+    strategy: Strategy,
+    rate: Option<f64>,
+    chunk: usize,
+    layout: Option<String>,
+    dry_run: bool,
+    help: bool,
+}
+
+/// What went wrong, and whether to show the usage
+enum Failure {
+    Usage(String),
+    Error(String),
+}
+
+impl From<winput_stuffer::Error> for Failure {
+    fn from(e: winput_stuffer::Error) -> Self {
+        Self::Error(e.to_string())
+    }
+}
+
+fn usage<T>(message: impl Into<String>) -> Result<T, Failure> {
+    Err(Failure::Usage(message.into()))
+}
+
+/// Splits the options, which go before the command, from the command and its arguments. `--` ends the options early.
+fn parse_options(args: Vec<String>) -> Result<(Options, Vec<String>), Failure> {
+    let mut options = Options{chunk: 1, ..Default::default()};
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        let mut value = |name: &str| args.next().ok_or_else(|| Failure::Usage(format!("{} needs a value", name)));
+        match arg.as_str() {
+            "--delay" => {
+                let delay = value("--delay")?;
+                options.delay = parse_duration(&delay).ok_or_else(|| Failure::Usage(format!("bad --delay {:?}", delay)))?;
+            },
+            "--strategy" => options.strategy = match value("--strategy")?.as_str() {
+                "vk" => Strategy::Vk,
+                "unicode" => Strategy::Unicode,
+                "paste" => Strategy::Paste,
+                other => return usage(format!("unknown --strategy {:?}, expected vk, unicode or paste", other)),
+            },
+            "--rate" => {
+                let rate = value("--rate")?;
+                options.rate = Some(rate.parse().ok().filter(|r: &f64| r.is_finite() && *r > 0.0).ok_or_else(|| Failure::Usage(format!("bad --rate {:?}", rate)))?);
+            },
+            "--chunk" => {
+                let chunk = value("--chunk")?;
+                options.chunk = chunk.parse().ok().filter(|c| *c > 0).ok_or_else(|| Failure::Usage(format!("bad --chunk {:?}", chunk)))?;
+            },
+            "--layout" => options.layout = Some(value("--layout")?),
+            "--dry-run" => options.dry_run = true,
+            "-h" | "--help" => options.help = true,
+            "--" => break,
+            _ => return usage(format!("unknown option {}", arg)),
+        }
+    }
+    let rest = args.collect();
+    // So slow that there'd be no end to the wait between chunks
+    if let Some(rate) = options.rate.filter(|rate| Duration::try_from_secs_f64(options.chunk as f64 / rate).is_err()) {
+        return usage(format!("--rate {:?} is too slow", rate));
+    }
+    Ok((options, rest))
+}
+
+fn read_text(path: &str) -> Result<String, Failure> {
+    let res = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    res.map_err(|e| Failure::Error(format!("can't read {}: {}", path, e)))
+}
+
+/// Every character the layout has a key for, with the key and the modifiers it's typed with, in order
+fn dump_layout(layout: &KeyboardLayout) -> String {
+    let mut chars:Vec<(&char, &(u8, u8))> = layout.char_to_vk_ss().iter().collect();
+    chars.sort_unstable();
+    let mut out = format!("layout {:08x}\n", layout.id().0);
+    for (c, (vk, ss)) in chars {
+        let mut vks = layout.ss_to_vks().get(ss).cloned().unwrap_or_default();
+        vks.push(*vk);
+        out += &format!("{:?}\t{}\n", c, Combo{vks});
+    }
+    out
+}
+
+/// Splits the inputs into chunks with the sleeps `--rate` and `--chunk` ask for between them
+fn pace(inputs: Vec<Input>, options: &Options) -> Result<Timeline, Failure> {
+    let mut timeline = Timeline::default();
+    let Some(rate) = options.rate else {
+        timeline.push_inputs(inputs);
+        return Ok(timeline);
+    };
+    let interval = Pacing::chars_per_second(rate, options.chunk)?.chunk_interval();
+    for (i, chunk) in chunks(&inputs, options.chunk).into_iter().enumerate() {
+        if i > 0 {
+            timeline.push_sleep(interval);
+        }
+        timeline.steps.push(Step::Inputs(chunk.to_vec()));
+    }
+    Ok(timeline)
+}
+
+fn run(args: Vec<String>) -> Result<(), Failure> {
+    let (options, args) = parse_options(args)?;
+    if args.is_empty() || options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let layout = match &options.layout {
+        Some(klid) => KeyboardLayout::bundled(klid).ok_or_else(|| Failure::Usage(format!("there's no bundled layout {:?}", klid)))?,
+        None => KeyboardLayout::current(),
+    };
+    let state = KeyState::current();
+    let text_options = TextOptions::default();
+    let args:Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let mut timeline = Timeline::default();
+    if !options.delay.is_zero() {
+        timeline.push_sleep(options.delay);
+    }
+    let mut statement = |statement| {
+        let script = Script{statements: vec![statement]};
+        timeline.steps.extend(script.compile(&layout, &state, &text_options).steps);
+    };
+    let key = |name: &str| key_vk(name, &layout).map_err(Failure::from);
+    match args[..] {
+        ["type", ref words @ ..] => {
+            let text = match words {
+                ["--file", path] => read_text(path)?,
+                ["--file", ..] => return usage("type --file needs exactly one PATH"),
+                [] => return usage("type needs some TEXT"),
+                _ => words.join(" "),
+            };
+            if options.strategy == Strategy::Paste {
+                return paste(&text, &timeline, &options);
+            }
+            let mut inputs = vec![];
+            match options.strategy {
+                Strategy::Unicode => inputs_for_text_unicode(&text, &layout, &text_options, &mut inputs),
+                _ => winput_stuffer::send::inputs_for_text_with_options(&text, &layout, &state, &text_options, &mut inputs),
+            }
+            timeline.steps.extend(pace(inputs, &options)?.steps);
+        },
+        ["key", name] => statement(Statement::Key(Combo{vks: vec![key(name)?]})),
+        ["key", name, "--down"] => statement(Statement::Down(key(name)?)),
+        ["key", name, "--up"] => statement(Statement::Up(key(name)?)),
+        ["combo", combo] => statement(Statement::Key(Combo::parse(combo, &layout)?)),
+        ["mouse", "move", dx, dy] => match (dx.parse(), dy.parse()) {
+            (Ok(dx), Ok(dy)) => statement(Statement::Move{dx, dy}),
+            _ => return usage(format!("bad distance {} {}", dx, dy)),
+        },
+        ["mouse", "click"] => statement(Statement::Click(MouseButton::Left)),
+        ["mouse", "click", button] => statement(Statement::Click(match button {
+            "left" => MouseButton::Left,
+            "right" => MouseButton::Right,
+            "middle" => MouseButton::Middle,
+            "x1" => MouseButton::X1,
+            "x2" => MouseButton::X2,
+            _ => return usage(format!("unknown mouse button {:?}", button)),
+        })),
+        ["mouse", "scroll", clicks] => statement(Statement::Scroll(clicks.parse().map_err(|_| Failure::Usage(format!("bad number of clicks {:?}", clicks)))?)),
+        ["layout", "dump"] => {
+            print!("{}", dump_layout(&layout));
+            return Ok(());
+        },
+        _ => return usage(format!("unknown command {:?}", args.join(" "))),
+    }
+
+    if options.dry_run {
+        print!("{}", timeline);
+        return Ok(());
+    }
+    timeline.run(0, &mut default_sink()?, &mut SystemClock::new())?;
+    Ok(())
+}
+
+/// Pastes through the clipboard after whatever is already in `timeline`, which is only the start delay
+fn paste(text: &str, timeline: &Timeline, options: &Options) -> Result<(), Failure> {
+    let paste = PasteOptions::default();
+    if options.dry_run {
+        print!("{}", timeline);
+        println!("paste {:?}", text);
+        println!("send {}", Combo{vks: paste.shortcut.vks().to_vec()}.inputs(None).iter().map(Input::to_string).collect::<Vec<_>>().join(", "));
+        return Ok(());
+    }
+    timeline.run(0, &mut default_sink()?, &mut SystemClock::new())?;
+    paste_text(text, &paste, None, &mut default_sink()?, &mut default_clipboard()?)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("winput-stuffer: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        },
+        Err(Failure::Error(message)) => {
+            eprintln!("winput-stuffer: {}", message);
+            ExitCode::FAILURE
+        },
+    }
+}
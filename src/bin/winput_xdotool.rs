@@ -0,0 +1,302 @@
+//! Runs xdotool style command chains, so shell scripts written for xdotool work here too.
+//!
+//! Run `winput-xdotool --help` for the usage.
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use winput_stuffer::KeyboardLayout;
+use winput_stuffer::combo::{key_vk, Combo};
+use winput_stuffer::input::{Input, MouseButton, MouseInput, MouseInputEnum, MouseMovement, WHEEL_DELTA};
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::monitor::Monitors;
+use winput_stuffer::pace::{strokes, SystemClock};
+use winput_stuffer::script::Timeline;
+use winput_stuffer::send::{inputs_for_text_with_options, TextOptions};
+use winput_stuffer::sink::default_sink;
+
+const USAGE:&str = "\
+Usage: winput-xdotool [--layout KLID] [--dry-run] COMMAND [ARGS]... [COMMAND [ARGS]...]...
+
+Commands, which can be chained one after another like in xdotool:
+  type [--delay MS] [--clearmodifiers] [--file PATH] TEXT...
+  key [--delay MS] [--repeat N] [--repeat-delay MS] KEYSEQUENCE...
+  keydown [--delay MS] KEYSEQUENCE...
+  keyup [--delay MS] KEYSEQUENCE...
+  mousemove [--screen N] X Y
+  mousemove_relative [--sync] [--] DX DY
+  click [--repeat N] [--delay MS] BUTTON
+  mousedown BUTTON
+  mouseup BUTTON
+  sleep SECONDS
+
+Key sequences are X keysym names joined with +, like ctrl+alt+Delete or Return.
+mousemove goes to a pixel of the desktop, where 0,0 is the top-left of the primary monitor, or of monitor N counting from 0 for the primary one.
+Buttons are 1 (left), 2 (middle), 3 (right), 4 and 5 (wheel up and down), 6 and 7 (wheel left and right), 8 and 9 (back and forward).
+
+Options:
+  --layout KLID             Plan with a bundled layout, eg 00000407, instead of the current one
+  --dry-run                 Print the inputs instead of sending them
+  -h, --help                Show this help
+";
+
+const COMMANDS:[&str; 10] = ["type", "key", "keydown", "keyup", "mousemove", "mousemove_relative", "click", "mousedown", "mouseup", "sleep"];
+
+/// xdotool waits this long between keystrokes unless told otherwise
+const DEFAULT_KEY_DELAY:Duration = Duration::from_millis(12);
+/// and this long between repeated clicks
+const DEFAULT_CLICK_DELAY:Duration = Duration::from_millis(100);
+
+/// X keysyms that aren't in the layout's `keyname_to_vk` under the same name, lower case
+const KEYSYM_ALIASES:[(&str, &str); 5] = [
+    ("meta", "alt_l"),
+    ("meta_l", "alt_l"),
+    ("meta_r", "alt_r"),
+    ("iso_level3_shift", "alt_r"),
+    ("sleep", "standby"),
+];
+
+fn keysym_name(name: &str) -> String {
+    let lower = name.to_lowercase();
+    // XF86AudioMute and friends are named without the prefix
+    let lower = lower.strip_prefix("xf86").map(str::to_string).unwrap_or(lower);
+    KEYSYM_ALIASES.iter().find(|(alias, _)| *alias == lower).map_or(lower, |(_, name)| name.to_string())
+}
+
+/// The keys of a sequence like "ctrl+alt+Delete", in the order xdotool presses them. A single character brings along the modifiers that type it, so "A" is Shift+A.
+fn sequence(seq: &str, layout: &KeyboardLayout) -> Result<Combo, String> {
+    let mut vks = vec![];
+    for name in seq.split('+') {
+        if name.is_empty() {
+            return Err(format!("empty key name in {:?}", seq));
+        }
+        let mut chars = name.chars();
+        let typed = match (chars.next(), chars.next()) {
+            (Some(c), None) => layout.char_to_vk_ss().get(&c),
+            _ => None,
+        };
+        if let Some((vk, ss)) = typed {
+            vks.extend(layout.ss_to_vks().get(ss).into_iter().flatten());
+            vks.push(*vk);
+        } else {
+            vks.push(key_vk(&keysym_name(name), layout).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(Combo{vks})
+}
+
+fn mouse(e: MouseInputEnum) -> Input {
+    Input::from_mouse(&MouseInput{e, msg: None, time: None})
+}
+
+/// The inputs for pressing (or releasing) an xdotool button number. The wheel "buttons" turn the wheel one click when pressed and do nothing when released.
+fn button(number: &str, button_up: bool) -> Result<Vec<Input>, String> {
+    let wheel = |horizontal, amount: i32| if button_up { vec![] } else { vec![mouse(MouseInputEnum::Wheel{horizontal, amount})] };
+    let which = match number {
+        "1" => MouseButton::Left,
+        "2" => MouseButton::Middle,
+        "3" => MouseButton::Right,
+        "4" => return Ok(wheel(false, WHEEL_DELTA.into())),
+        "5" => return Ok(wheel(false, -i32::from(WHEEL_DELTA))),
+        "6" => return Ok(wheel(true, -i32::from(WHEEL_DELTA))),
+        "7" => return Ok(wheel(true, WHEEL_DELTA.into())),
+        "8" => MouseButton::X1,
+        "9" => MouseButton::X2,
+        _ => return Err(format!("unknown button {:?}, expected 1 to 9", number)),
+    };
+    Ok(vec![mouse(MouseInputEnum::Button{which, button_up})])
+}
+
+fn sleep(timeline: &mut Timeline, duration: Duration) {
+    if !duration.is_zero() {
+        timeline.push_sleep(duration);
+    }
+}
+
+fn millis(value: &str) -> Result<Duration, String> {
+    value.parse().map(Duration::from_millis).map_err(|_| format!("expected milliseconds, not {:?}", value))
+}
+
+fn read_text(path: &str) -> Result<String, String> {
+    let res = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    res.map_err(|e| format!("can't read {}: {}", path, e))
+}
+
+/// Options that a command was given
+#[derive(Debug,Default)]
+struct CommandOptions {
+    delay: Option<Duration>,
+    repeat: u32,
+    repeat_delay: Duration,
+    clear_modifiers: bool,
+    file: Option<String>,
+    screen: Option<usize>,
+}
+
+/// Adds one command, from the front of `args`, to the timeline
+fn command(args: &mut std::iter::Peekable<std::vec::IntoIter<String>>, layout: &KeyboardLayout, state: &KeyState, timeline: &mut Timeline) -> Result<(), String> {
+    let name = args.next().unwrap_or_default();
+    if !COMMANDS.contains(&name.as_str()) {
+        return Err(format!("unknown command {:?}", name));
+    }
+    let mut options = CommandOptions{repeat: 1, ..Default::default()};
+    while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+        let mut value = || args.next().ok_or_else(|| format!("{} {} needs a value", name, option));
+        match (name.as_str(), option.as_str()) {
+            (_, "--") => break,
+            ("type" | "key" | "keydown" | "keyup" | "click", "--delay") => options.delay = Some(millis(&value()?)?),
+            ("key" | "click", "--repeat") => options.repeat = value()?.parse().map_err(|_| format!("{} --repeat needs a number", name))?,
+            ("key", "--repeat-delay") => options.repeat_delay = millis(&value()?)?,
+            ("type", "--clearmodifiers") => options.clear_modifiers = true,
+            ("type", "--file") => options.file = Some(value()?),
+            ("mousemove" | "mousemove_relative", "--sync") => (),
+            ("mousemove", "--screen") => options.screen = Some(value()?.parse().map_err(|_| "mousemove --screen needs a monitor number".to_string())?),
+            (_, "--window" | "--clearmodifiers" | "--screen" | "--polar" | "--args" | "--terminator" | "--repeat-delay" | "--repeat" | "--delay") => {
+                return Err(format!("{} {} isn't supported", name, option));
+            },
+            _ => return Err(format!("unknown option {} for {}", option, name)),
+        }
+    }
+    // Commands that take a list of arguments take them up to the next command
+    let mut list = || -> Vec<String> {
+        let mut list:Vec<String> = args.next().into_iter().collect();
+        while let Some(arg) = args.next_if(|arg| !COMMANDS.contains(&arg.as_str())) {
+            list.push(arg);
+        }
+        list
+    };
+    let key_delay = options.delay.unwrap_or(DEFAULT_KEY_DELAY);
+
+    match name.as_str() {
+        "type" => {
+            let texts = match options.file.take() {
+                Some(path) => vec![read_text(&path)?],
+                None => list(),
+            };
+            if texts.is_empty() {
+                return Err("type needs some text".into());
+            }
+            let text_options = TextOptions{release_held_modifiers: options.clear_modifiers, ..Default::default()};
+            let mut inputs = vec![];
+            inputs_for_text_with_options(&texts.concat(), layout, state, &text_options, &mut inputs);
+            for (i, stroke) in strokes(&inputs).into_iter().enumerate() {
+                if i > 0 {
+                    sleep(timeline, key_delay);
+                }
+                timeline.push_inputs(stroke.iter().copied());
+            }
+        },
+        "key" | "keydown" | "keyup" => {
+            let sequences = list();
+            if sequences.is_empty() {
+                return Err(format!("{} needs a key sequence", name));
+            }
+            let combos = sequences.iter().map(|seq| sequence(seq, layout)).collect::<Result<Vec<_>, _>>()?;
+            for r in 0..options.repeat {
+                if r > 0 {
+                    sleep(timeline, key_delay + options.repeat_delay);
+                }
+                for (i, combo) in combos.iter().enumerate() {
+                    if i > 0 {
+                        sleep(timeline, key_delay);
+                    }
+                    timeline.push_inputs(match name.as_str() {
+                        "keydown" => combo.down_inputs(None),
+                        "keyup" => combo.up_inputs(None),
+                        _ => combo.inputs(None),
+                    });
+                }
+            }
+        },
+        "mousemove" | "mousemove_relative" => {
+            let mut number = || -> Result<i32, String> {
+                let arg = args.next().ok_or_else(|| format!("{} needs two numbers of pixels", name))?;
+                arg.parse().map_err(|_| format!("expected a number of pixels, not {:?}", arg))
+            };
+            let (x, y) = (number()?, number()?);
+            let m = if name == "mousemove" {
+                let monitors = Monitors::current().map_err(|e| e.to_string())?;
+                match options.screen {
+                    Some(monitor) => monitors.move_to_monitor_relative(monitor, x, y),
+                    None => monitors.move_to_pixel(x, y),
+                }.map_err(|e| e.to_string())?
+            } else {
+                MouseMovement::Relative{dx: x, dy: y}
+            };
+            timeline.push_inputs([mouse(MouseInputEnum::Move{m, coalesce: false})]);
+        },
+        "click" | "mousedown" | "mouseup" => {
+            let number = args.next().ok_or_else(|| format!("{} needs a button", name))?;
+            let inputs = match name.as_str() {
+                "mousedown" => button(&number, false)?,
+                "mouseup" => button(&number, true)?,
+                _ => [button(&number, false)?, button(&number, true)?].concat(),
+            };
+            for r in 0..options.repeat {
+                if r > 0 {
+                    sleep(timeline, options.delay.unwrap_or(DEFAULT_CLICK_DELAY));
+                }
+                timeline.push_inputs(inputs.iter().copied());
+            }
+        },
+        "sleep" => {
+            let seconds = args.next().ok_or("sleep needs a number of seconds")?;
+            let duration = seconds.parse().ok().and_then(|s| Duration::try_from_secs_f64(s).ok()).ok_or_else(|| format!("expected seconds, not {:?}", seconds))?;
+            sleep(timeline, duration);
+        },
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter().peekable();
+    let mut layout = None;
+    let mut dry_run = false;
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        match arg.as_str() {
+            "--layout" => {
+                let klid = args.next().ok_or("--layout needs a KLID")?;
+                layout = Some(KeyboardLayout::bundled(&klid).ok_or_else(|| format!("there's no bundled layout {:?}", klid))?);
+            },
+            "--dry-run" => dry_run = true,
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return Ok(());
+            },
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    if args.peek().is_none() {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let layout = layout.unwrap_or_else(KeyboardLayout::current);
+    let state = KeyState::current();
+
+    // Everything is checked before anything is sent
+    let mut timeline = Timeline::default();
+    while args.peek().is_some() {
+        command(&mut args, &layout, &state, &mut timeline)?;
+    }
+    if dry_run {
+        print!("{}", timeline);
+        return Ok(());
+    }
+    timeline.run(0, &mut default_sink().map_err(|e| e.to_string())?, &mut SystemClock::new()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("winput-xdotool: {}", message);
+            ExitCode::FAILURE
+        },
+    }
+}
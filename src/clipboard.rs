@@ -0,0 +1,203 @@
+//! Places that text can be put for pasting, see `send::paste_text`.
+
+use crate::Result;
+
+/// Somewhere that holds text to be pasted. Only text is dealt with, so anything else that was on the clipboard is lost when the text is restored.
+pub trait Clipboard {
+    /// Returns None if there's no text on the clipboard.
+    fn get_text(&mut self) -> Result<Option<String>>;
+    fn set_text(&mut self, text: &str) -> Result<()>;
+    fn clear(&mut self) -> Result<()>;
+
+    /// Whether there's nothing at all on the clipboard, not even something other than text.
+    fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.get_text()?.is_none())
+    }
+}
+
+impl<C: Clipboard + ?Sized> Clipboard for &mut C {
+    fn get_text(&mut self) -> Result<Option<String>> {
+        (**self).get_text()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        (**self).set_text(text)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        (**self).clear()
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        (**self).is_empty()
+    }
+}
+
+impl<C: Clipboard + ?Sized> Clipboard for Box<C> {
+    fn get_text(&mut self) -> Result<Option<String>> {
+        (**self).get_text()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        (**self).set_text(text)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        (**self).clear()
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        (**self).is_empty()
+    }
+}
+
+/// The clipboard that `send::send_text_with_options` pastes with, which on windows is `Win32Clipboard`.
+#[cfg(windows)]
+pub fn default_clipboard() -> Result<Box<dyn Clipboard>> {
+    Ok(Box::new(Win32Clipboard))
+}
+
+/// The clipboard that `send::send_text_with_options` pastes with. There isn't one for this platform, so this always fails.
+#[cfg(not(windows))]
+pub fn default_clipboard() -> Result<Box<dyn Clipboard>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "There is no clipboard backend for this platform").into())
+}
+
+/// A clipboard that's just a string in memory. `history` records every text that was set, so tests can check what would have been pasted.
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct MemoryClipboard {
+    pub text: Option<String>,
+    pub history: Vec<Option<String>>,
+    /// Pretends there's something other than text on the clipboard, such as an image, until text is set or it's cleared
+    pub other_data: bool,
+}
+
+impl MemoryClipboard {
+    pub fn with_text(text: &str) -> Self {
+        Self{text: Some(text.into()), ..Default::default()}
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<Option<String>> {
+        Ok(self.text.clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        self.text = Some(text.into());
+        self.other_data = false;
+        self.history.push(self.text.clone());
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.text = None;
+        self.other_data = false;
+        self.history.push(None);
+        Ok(())
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.text.is_none() && !self.other_data)
+    }
+}
+
+/// The windows clipboard, as CF_UNICODETEXT.
+#[cfg(windows)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Win32Clipboard;
+
+#[cfg(windows)]
+mod win32 {
+    use std::io;
+
+    use crate::Result;
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::System::DataExchange as de_sys;
+    use windows::Win32::System::Memory as mem_sys;
+
+    // From winuser.h, to avoid pulling in all of SystemServices for one constant
+    const CF_UNICODETEXT:u32 = 13;
+
+    /// Keeps the clipboard open until dropped
+    struct OpenClipboard;
+
+    impl OpenClipboard {
+        fn open() -> io::Result<Self> {
+            // Without a window the clipboard ends up with no owner, which the docs warn makes SetClipboardData fail, but in practice it works and there's no window of ours to give it
+            if unsafe { de_sys::OpenClipboard(HWND(0)) }.as_bool() {
+                Ok(Self)
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        }
+    }
+
+    impl Drop for OpenClipboard {
+        fn drop(&mut self) {
+            unsafe { de_sys::CloseClipboard() };
+        }
+    }
+
+    impl super::Clipboard for super::Win32Clipboard {
+        fn get_text(&mut self) -> Result<Option<String>> {
+            let _open = OpenClipboard::open()?;
+            if !unsafe { de_sys::IsClipboardFormatAvailable(CF_UNICODETEXT) }.as_bool() {
+                return Ok(None);
+            }
+            let handle = unsafe { de_sys::GetClipboardData(CF_UNICODETEXT) }.map_err(io::Error::from)?;
+            let ptr = unsafe { mem_sys::GlobalLock(handle.0) } as *const u16;
+            if ptr.is_null() {
+                return Err(io::Error::last_os_error().into());
+            }
+            let len = unsafe { mem_sys::GlobalSize(handle.0) } / 2;
+            let wide = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let end = wide.iter().position(|wc| *wc == 0).unwrap_or(len);
+            let text = String::from_utf16_lossy(&wide[..end]);
+            unsafe { mem_sys::GlobalUnlock(handle.0) };
+            Ok(Some(text))
+        }
+
+        fn set_text(&mut self, text: &str) -> Result<()> {
+            let wide:Vec<u16> = text.encode_utf16().chain([0]).collect();
+            let _open = OpenClipboard::open()?;
+            if !unsafe { de_sys::EmptyClipboard() }.as_bool() {
+                return Err(io::Error::last_os_error().into());
+            }
+            let mem = unsafe { mem_sys::GlobalAlloc(mem_sys::GMEM_MOVEABLE, wide.len() * 2) };
+            if mem == 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+            let ptr = unsafe { mem_sys::GlobalLock(mem) } as *mut u16;
+            if ptr.is_null() {
+                let err = io::Error::last_os_error();
+                unsafe { mem_sys::GlobalFree(mem) };
+                return Err(err.into());
+            }
+            unsafe {
+                std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+                mem_sys::GlobalUnlock(mem);
+            }
+            // Once this succeeds the memory belongs to the clipboard
+            if let Err(e) = unsafe { de_sys::SetClipboardData(CF_UNICODETEXT, HANDLE(mem)) } {
+                unsafe { mem_sys::GlobalFree(mem) };
+                return Err(io::Error::from(e).into());
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            let _open = OpenClipboard::open()?;
+            if unsafe { de_sys::EmptyClipboard() }.as_bool() {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error().into())
+            }
+        }
+
+        fn is_empty(&mut self) -> Result<bool> {
+            let _open = OpenClipboard::open()?;
+            Ok(unsafe { de_sys::CountClipboardFormats() } == 0)
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Key combinations written as strings, like "ctrl+shift+t" or "alt_l+tab".
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{Error, Result};
+use crate::input::Input;
+use crate::layout::KeyboardLayout;
+use crate::layout::maps::*;
+use crate::send::key_event;
+use crate::window_message::WindowMessage;
+
+const MODIFIERS:[u8; 11] = [
+    VK_SHIFT, VK_LSHIFT, VK_RSHIFT,
+    VK_CONTROL, VK_LCONTROL, VK_RCONTROL,
+    VK_MENU, VK_LMENU, VK_RMENU,
+    VK_LWIN, VK_RWIN,
+];
+
+pub fn is_modifier(vk: u8) -> bool {
+    MODIFIERS.contains(&vk)
+}
+
+/// Some keys held together: the modifiers, then the rest in the order they were written.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Combo {
+    pub vks: Vec<u8>,
+}
+
+impl Combo {
+    /// Parses keys joined with `+`, eg "ctrl+shift+t". Each key is a name from the layout's `keyname_to_vk` (case doesn't matter, and modifiers can be called ctrl, control, shift, alt, win or super) or a single character that the layout has a key for. A `+` at the end means the plus key itself, as in "ctrl++".
+    pub fn parse(combo: &str, layout: &KeyboardLayout) -> Result<Self> {
+        let mut names:Vec<&str> = combo.split('+').map(str::trim).collect();
+        let trimmed = combo.trim();
+        if trimmed == "+" || trimmed.ends_with("++") {
+            names.truncate(names.len() - 2);
+            names.push("+");
+        }
+        let mut vks = vec![];
+        for name in names {
+            if name.is_empty() {
+                return Err(Error::InvalidCombo(format!("{:?} has an empty key name", combo)));
+            }
+            let vk = key_vk(name, layout)?;
+            if vks.contains(&vk) {
+                return Err(Error::InvalidCombo(format!("{:?} has {:?} more than once", combo, name)));
+            }
+            vks.push(vk);
+        }
+        // Stable, so modifiers and other keys each keep the order they were written in
+        vks.sort_by_key(|vk| !is_modifier(*vk));
+        Ok(Self{vks})
+    }
+
+    /// Every key going down in order, then coming up in reverse order.
+    pub fn inputs(&self, msg: Option<WindowMessage>) -> Vec<Input> {
+        let mut res = self.down_inputs(msg);
+        res.extend(self.up_inputs(msg));
+        res
+    }
+
+    pub fn down_inputs(&self, msg: Option<WindowMessage>) -> Vec<Input> {
+        self.vks.iter().map(|vk| Input::from_keyboard(&key_event(*vk, true, msg))).collect()
+    }
+
+    pub fn up_inputs(&self, msg: Option<WindowMessage>) -> Vec<Input> {
+        self.vks.iter().rev().map(|vk| Input::from_keyboard(&key_event(*vk, false, msg))).collect()
+    }
+}
+
+impl fmt::Display for Combo {
+    /// Writes the combo with the virtual key names from windows, eg "LCONTROL+T"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, vk) in self.vks.iter().enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+            match VIRTUAL_KEY_TO_CODE.get_by_right(vk) {
+                Some(name) => write!(f, "{}", name)?,
+                None => write!(f, "{:#04x}", vk)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Looks up one key of a combo, as a name from `keyname_to_vk` or else a single character that the layout types without dead keys. Unknown names fail with `Error::UnknownKeyName`.
+pub fn key_vk(name: &str, layout: &KeyboardLayout) -> Result<u8> {
+    let lower:Cow<'static, str> = name.to_lowercase().into();
+    if let Some(vk) = layout.keyname_to_vk().get(&lower) {
+        return Ok(*vk);
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let by_char = |c| layout.char_to_vk_ss().get(&c).map(|(vk, _)| *vk);
+        // Letters are found lower case, but "ctrl+T" should still work
+        if let Some(vk) = by_char(c).or_else(|| c.to_lowercase().next().and_then(by_char)) {
+            return Ok(vk);
+        }
+    }
+    Err(Error::UnknownKeyName(name.into()))
+}
+
+/// Parses a key combo in Plover's syntax, as used by `{#...}` strokes, into keys going down (true) and up (false). Key names are separated by spaces and pressed then released in turn, unless followed by `(`, in which case the key is held down until the matching `)`, eg "alt_l(tab tab) control_l(c)". Names are looked up lower case with `key_name_to_key_code`.
+///
+/// This is a port of `parse_key_combo` from Plover's `key_combo.py`, including the error messages, which mark where the problem is with square brackets.
+pub fn parse_plover_combo<T: Clone + PartialEq>(
+    combo: &str,
+    key_name_to_key_code: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<(T, bool)>> {
+    let error_at = |start: usize, end: usize, details: String| {
+        Error::InvalidCombo(format!("{} in \"{}[{}]{}\"", details, &combo[..start], &combo[start..end], &combo[end..]))
+    };
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut key_events = vec![];
+    let mut down_keys:Vec<T> = vec![];
+    let mut chars = combo.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == ')' {
+            let key_code = down_keys.pop().ok_or_else(|| error_at(start, start + 1, "unbalanced \")\"".into()))?;
+            key_events.push((key_code, false));
+            continue;
+        }
+        if !is_word(c) {
+            return Err(error_at(start, start + c.len_utf8(), format!("invalid character \"{}\"", c)));
+        }
+        let mut end = start + c.len_utf8();
+        while let Some((i, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+            end = i + c.len_utf8();
+        }
+        let name_end = end;
+        let key_name = combo[start..end].to_lowercase();
+        // A "(" after the name, even with spaces in between, holds the key down
+        let rest = &combo[end..];
+        let hold = rest.trim_start().starts_with('(');
+        if hold {
+            end = combo.len() - rest.trim_start().len() + 1;
+            while chars.next_if(|(i, _)| *i < end).is_some() {}
+        }
+        let key_code = key_name_to_key_code(&key_name).ok_or_else(|| error_at(start, name_end, "unknown key".into()))?;
+        if down_keys.contains(&key_code) {
+            return Err(error_at(start, end, format!("key \"{}\" already pressed", key_name)));
+        }
+        key_events.push((key_code.clone(), true));
+        if hold {
+            down_keys.push(key_code);
+        } else {
+            key_events.push((key_code, false));
+        }
+    }
+    if !down_keys.is_empty() {
+        return Err(error_at(combo.len(), combo.len(), "unbalanced \"(\"".into()));
+    }
+    Ok(key_events)
+}
+
+/// The inputs for a Plover style combo, with keys looked up like `key_vk` does, so that single characters like "a" name the key that types them as they do in Plover. See `parse_plover_combo`.
+pub fn plover_combo_inputs(combo: &str, layout: &KeyboardLayout, msg: Option<WindowMessage>) -> Result<Vec<Input>> {
+    let key_events = parse_plover_combo(combo, |name| key_vk(name, layout).ok())?;
+    Ok(key_events.into_iter().map(|(vk, down)| Input::from_keyboard(&key_event(vk, down, msg))).collect())
+}
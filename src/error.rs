@@ -0,0 +1,92 @@
+//! The errors this crate returns instead of panicking.
+
+use std::fmt;
+use std::io;
+
+use crate::sink::SendReport;
+
+#[derive(Debug)]
+pub enum Error {
+    /// There's no foreground window to ask for its keyboard layout, which happens while a window is losing activation.
+    NoForegroundWindow,
+    /// A key name that the layout doesn't have, eg in `send::send_key`
+    UnknownKeyName(String),
+    /// A key combo that can't be parsed, such as "ctrl++shift" or one with the same key twice
+    InvalidCombo(String),
+    /// A script or send string that can't be run, with the 1-based line and column (in characters) of the problem
+    Syntax{line: usize, column: usize, message: String},
+    /// A pixel that no monitor shows, eg in `monitor::Monitors::move_to_pixel`
+    OffScreen{x: i32, y: i32},
+    /// A monitor index past the end of `monitor::Monitors::monitors`
+    NoSuchMonitor(usize),
+    /// Virtual key codes have to be in the range 1..=254
+    VirtualKeyOutOfRange(u16),
+    /// None of the inputs were accepted and Windows didn't say why, which is nearly always UIPI stopping input to a program running with more privileges than us.
+    BlockedByUipi,
+    /// Only the start of a batch was accepted, even after any retries. The report says how much, and which keys were let go afterwards.
+    PartialSend(SendReport),
+    /// Something that couldn't be read, such as a bad layout snapshot
+    InvalidData(String),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// A `Syntax` error at byte offset `at` of `source`
+    pub fn syntax_at(source: &str, at: usize, message: impl Into<String>) -> Self {
+        let before = &source[..at];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self::Syntax{
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoForegroundWindow => write!(f, "There is no foreground window"),
+            Self::UnknownKeyName(name) => write!(f, "Unknown key name {:?}", name),
+            Self::InvalidCombo(msg) => write!(f, "Invalid key combo: {}", msg),
+            Self::Syntax{line, column, message} => write!(f, "{}:{}: {}", line, column, message),
+            Self::OffScreen{x, y} => write!(f, "There is no monitor at {},{}", x, y),
+            Self::NoSuchMonitor(n) => write!(f, "There is no monitor {}", n),
+            Self::VirtualKeyOutOfRange(vk) => write!(f, "Virtual key {:#x} is outside the range 1..=254", vk),
+            Self::BlockedByUipi => write!(f, "No input was accepted, most likely because UIPI blocked it"),
+            Self::PartialSend(report) => write!(f, "Only {} of {} inputs were accepted", report.accepted, report.total),
+            Self::InvalidData(msg) => write!(f, "{}", msg),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// So that code which deals in `io::Result` can keep using `?`
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::BlockedByUipi => io::Error::new(io::ErrorKind::PermissionDenied, e),
+            Error::InvalidData(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            Error::UnknownKeyName(_) | Error::InvalidCombo(_) | Error::Syntax{..} | Error::OffScreen{..} | Error::NoSuchMonitor(_) | Error::VirtualKeyOutOfRange(_) => io::Error::new(io::ErrorKind::InvalidInput, e),
+            _ => io::Error::other(e),
+        }
+    }
+}
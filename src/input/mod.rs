@@ -0,0 +1,230 @@
+use std::fmt;
+use std::num::NonZeroU32;
+
+use crate::layout::maps::VIRTUAL_KEY_TO_CODE;
+
+#[cfg(windows)]
+mod win32;
+#[cfg(windows)]
+pub use win32::*;
+
+pub const WHEEL_DELTA:u8 = 120;
+
+/// A windows virtual-key code, as used in `KEYBDINPUT::wVk`
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct VirtualKey(pub u16);
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct MouseInput {
+    pub e: MouseInputEnum,
+    pub msg: Option<super::window_message::WindowMessage>,
+    /// The time stamp for the event, in milliseconds. If this parameter is None, the system will provide its own time stamp.
+    pub time: Option<NonZeroU32>,
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum MouseInputEnum {
+    Button{which: MouseButton, button_up: bool},
+    Move{m: MouseMovement, coalesce: bool},
+    /// When vertical (horizontal: false)
+    /// ```text
+    ///  ^ + positive values are forward, away from the user
+    ///  |
+    ///  v - negative values are backward, towards the user
+    /// ```
+    /// 
+    /// When horizontal: true
+    /// ```text
+    /// negative values point left
+    ///   |
+    /// < - ................................ + >
+    ///                                      |
+    ///              positive values point right
+    /// ```
+    /// 
+    /// In both cases, one wheel click is 120 (WHEEL_DELTA)
+    Wheel{horizontal: bool, amount: i32,},
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    X1,
+    X2,
+}
+
+/// To move to a pixel instead of working out normalized coordinates by hand, see `monitor::Monitors::move_to_pixel`.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum MouseMovement {
+    /// Move the mouse to the given coordinates.
+    /// Coordinates are absolute. 0,0 is the top-left of the primary monitor and 65535,65535 is the bottom-right of the primary monitor
+    AbsolutePrimaryMonitor{x: u16, y: u16},
+    /// Move the mouse to the given coordinates.
+    /// Coordinates are absolute. 0,0 is the top-left of the entire virtual desktop (all monitors) and 65535,65535 is the bottom-right of the virtual desktop
+    AbsoluteVirtualDesktop{x: u16, y: u16},
+    /// Move the mouse by the given delta x and delta y. Positive dx goes right, and positive dy goes down
+    Relative{dx: i32, dy: i32},
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct KeyboardInput {
+    pub e: KeyboardInputEnum,
+    pub key_up: bool,
+    pub msg: Option<super::window_message::WindowMessage>,
+    /// The time stamp for the event, in milliseconds. If this parameter is None, the system will provide its own time stamp.
+    pub time: Option<NonZeroU32>,
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum KeyboardInputEnum {
+    /// Must be in the range 1..=254
+    VirtualKeyCode{code: VirtualKey, extended: bool},
+    ScanCode{code: u16, extended: bool},
+    /// That's right, you have to encode your text as UTF-16 and if it's got two code units you have to send 2 events.
+    UnicodeCodeUnit(u16),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HardwareInput {
+    pub u_msg: u32,
+    pub w_param_l: u16,
+    pub w_param_h: u16,
+}
+
+/// A single keyboard, mouse, or hardware event, the platform-independent equivalent of an `INPUT` struct.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Input {
+    Mouse(MouseInput),
+    Keyboard(KeyboardInput),
+    Hardware(HardwareInput),
+}
+
+impl Input {
+    pub fn from_mouse(t: &MouseInput) -> Self {
+        Self::Mouse(*t)
+    }
+
+    pub fn from_keyboard(t: &KeyboardInput) -> Self {
+        Self::Keyboard(*t)
+    }
+
+    pub fn from_hardware(t: &HardwareInput) -> Self {
+        Self::Hardware(*t)
+    }
+
+    pub fn as_mouse(&self) -> Option<&MouseInput> {
+        match self {
+            Self::Mouse(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_keyboard(&self) -> Option<&KeyboardInput> {
+        match self {
+            Self::Keyboard(k) => Some(k),
+            _ => None,
+        }
+    }
+}
+
+/// Short descriptions for dry runs, eg "LCONTROL down" or "left button up"
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mouse(m) => write!(f, "{}", m),
+            Self::Keyboard(k) => write!(f, "{}", k),
+            Self::Hardware(h) => write!(f, "hardware message {:#x} ({:#x}, {:#x})", h.u_msg, h.w_param_l, h.w_param_h),
+        }
+    }
+}
+
+impl fmt::Display for KeyboardInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.e {
+            KeyboardInputEnum::VirtualKeyCode{code, ..} => match u8::try_from(code.0).ok().and_then(|vk| VIRTUAL_KEY_TO_CODE.get_by_right(&vk)) {
+                Some(name) => write!(f, "{}", name)?,
+                None => write!(f, "vk {:#04x}", code.0)?,
+            },
+            KeyboardInputEnum::ScanCode{code, extended: false} => write!(f, "sc {:#04x}", code)?,
+            KeyboardInputEnum::ScanCode{code, extended: true} => write!(f, "sc e0 {:#04x}", code)?,
+            KeyboardInputEnum::UnicodeCodeUnit(wc) => write!(f, "U+{:04X}", wc)?,
+        }
+        write!(f, " {}", if self.key_up { "up" } else { "down" })
+    }
+}
+
+impl fmt::Display for MouseInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.e {
+            MouseInputEnum::Button{which, button_up} => {
+                write!(f, "{} button {}", format!("{:?}", which).to_lowercase(), if button_up { "up" } else { "down" })
+            },
+            MouseInputEnum::Move{m: MouseMovement::Relative{dx, dy}, ..} => write!(f, "move by {},{}", dx, dy),
+            MouseInputEnum::Move{m: MouseMovement::AbsolutePrimaryMonitor{x, y}, ..} => write!(f, "move to {},{} of the primary monitor", x, y),
+            MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x, y}, ..} => write!(f, "move to {},{} of the virtual desktop", x, y),
+            MouseInputEnum::Wheel{horizontal: false, amount} => write!(f, "wheel {}", amount),
+            MouseInputEnum::Wheel{horizontal: true, amount} => write!(f, "horizontal wheel {}", amount),
+        }
+    }
+}
+
+impl From<MouseInput> for Input {
+    fn from(m: MouseInput) -> Self {
+        Self::Mouse(m)
+    }
+}
+
+impl From<KeyboardInput> for Input {
+    fn from(k: KeyboardInput) -> Self {
+        Self::Keyboard(k)
+    }
+}
+
+impl From<HardwareInput> for Input {
+    fn from(h: HardwareInput) -> Self {
+        Self::Hardware(h)
+    }
+}
+
+/// Keeps track of which keys a sequence of inputs has left down.
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct HeldKeys(Vec<KeyboardInput>);
+
+impl HeldKeys {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Notes a key going down or up. Anything other than a key is ignored.
+    pub fn track(&mut self, input: &Input) {
+        if let Some(ki) = input.as_keyboard() {
+            if ki.key_up {
+                self.0.retain(|held| held.e != ki.e);
+            } else if !self.0.iter().any(|held| held.e == ki.e) {
+                self.0.push(*ki);
+            }
+        }
+    }
+
+    pub fn track_all<'i>(&mut self, inputs: impl IntoIterator<Item = &'i Input>) {
+        for input in inputs {
+            self.track(input);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The key-downs of the keys that are still down, in the order they were pressed.
+    pub fn keys(&self) -> &[KeyboardInput] {
+        &self.0
+    }
+
+    /// Key-ups that let go of every key that's still down, in the opposite order they were pressed.
+    pub fn key_ups(&self) -> Vec<Input> {
+        self.0.iter().rev().map(|ki| Input::from_keyboard(&KeyboardInput{key_up: true, time: None, ..*ki})).collect()
+    }
+}
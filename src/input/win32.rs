@@ -1,19 +1,17 @@
 use std::mem::MaybeUninit;
 use std::io;
-use std::num::NonZeroU32;
 
 use windows::Win32::UI::Input::KeyboardAndMouse as km_sys;
 use windows::Win32::UI::WindowsAndMessaging as wm_sys;
 
-pub const WHEEL_DELTA:u8 = 120;
-
 pub use km_sys::{
     MOUSEINPUT,
     KEYBDINPUT,
     HARDWAREINPUT,
-    VIRTUAL_KEY as VirtualKey,
 };
 
+use super::*;
+
 #[derive(Debug, Copy, Clone)]
 #[repr(transparent)]
 pub struct MouseInputSys(MOUSEINPUT);
@@ -100,60 +98,6 @@ impl From<MouseInput> for MouseInputSys {
     }
 }
 
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub struct MouseInput {
-    pub e: MouseInputEnum,
-    pub msg: Option<super::window_message::WindowMessage>,
-    /// The time stamp for the event, in milliseconds. If this parameter is None, the system will provide its own time stamp.
-    pub time: Option<NonZeroU32>,
-}
-
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub enum MouseInputEnum {
-    Button{which: MouseButton, button_up: bool},
-    Move{m: MouseMovement, coalesce: bool},
-    /// When vertical (horizontal: false)
-    /// ```
-    ///  ^ + positive values are forward, away from the user
-    ///  |
-    ///  v - negative values are backward, towards the user
-    /// ```
-    /// 
-    /// When horizontal: true
-    /// ```
-    /// negative values point left
-    ///   |
-    /// < - ................................ + >
-    ///                                      |
-    ///              positive values point right
-    /// ```
-    /// 
-    /// In both cases, one wheel click is 120 (WHEEL_DELTA)
-    Wheel{horizontal: bool, amount: i32,},
-}
-
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub enum MouseButton {
-    Left,
-    Middle,
-    Right,
-    X1,
-    X2,
-}
-
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub enum MouseMovement {
-    /// Move the mouse to the given coordinates.
-    /// Coordinates are absolute. 0,0 is the top-left of the primary monitor and 65535,65535 is the bottom-right of the primary monitor
-    AbsolutePrimaryMonitor{x: u16, y: u16},
-    /// Move the mouse to the given coordinates.
-    /// Coordinates are absolute. 0,0 is the top-left of the entire virtual desktop (all monitors) and 65535,65535 is the bottom-right of the virtual desktop
-    AbsoluteVirtualDesktop{x: u16, y: u16},
-    /// Move the mouse by the given delta x and delta y. Positive dx goes right, and positive dy goes down
-    Relative{dx: i32, dy: i32},
-}
-
-
 
 
 
@@ -167,11 +111,13 @@ impl KeyboardInputSys {
     }
 }
 
-impl From<KeyboardInput> for KeyboardInputSys {
-    fn from(rusty: KeyboardInput) -> Self {
+impl TryFrom<KeyboardInput> for KeyboardInputSys {
+    type Error = crate::Error;
+
+    fn try_from(rusty: KeyboardInput) -> crate::Result<Self> {
         let mut inner:KEYBDINPUT = unsafe { std::mem::zeroed() };
         if let Some(msg) = rusty.msg {
-            inner.dwExtraInfo = msg.into_inner().try_into().unwrap();
+            inner.dwExtraInfo = msg.into_inner() as usize;
         }
         if let Some(time) = rusty.time {
             inner.time = time.into();
@@ -184,12 +130,12 @@ impl From<KeyboardInput> for KeyboardInputSys {
         match rusty.e {
             KeyboardInputEnum::VirtualKeyCode{code, extended} => {
                 if code.0 < 1 || code.0 > 254 {
-                    panic!("VirtualKeyCode outside acceptable range");
+                    return Err(crate::Error::VirtualKeyOutOfRange(code.0));
                 }
                 if extended {
                     inner.dwFlags |= km_sys::KEYEVENTF_EXTENDEDKEY
                 }
-                inner.wVk = code;
+                inner.wVk = km_sys::VIRTUAL_KEY(code.0);
             },
             KeyboardInputEnum::ScanCode{code, extended} => {
                 inner.wScan = code;
@@ -204,38 +150,10 @@ impl From<KeyboardInput> for KeyboardInputSys {
             }
         }
 
-        Self(inner)
+        Ok(Self(inner))
     }
 }
 
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub struct KeyboardInput {
-    pub e: KeyboardInputEnum,
-    pub key_up: bool,
-    pub msg: Option<super::window_message::WindowMessage>,
-    /// The time stamp for the event, in milliseconds. If this parameter is None, the system will provide its own time stamp.
-    pub time: Option<NonZeroU32>,
-}
-
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
-pub enum KeyboardInputEnum {
-    /// Must be in the range 1..=254
-    VirtualKeyCode{code: VirtualKey, extended: bool},
-    ScanCode{code: u16, extended: bool},
-    /// That's right, you have to encode your text as UTF-16 and if it's got two code units you have to send 2 events.
-    UnicodeCodeUnit(u16),
-}
-
-
-
-
-
-
-
-
-
-
-
 
 
 
@@ -259,31 +177,14 @@ impl From<HardwareInput> for HardwareInputSys {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-struct HardwareInput {
-    u_msg: u32,
-    w_param_l: u16,
-    w_param_h: u16,
-}
-
-
-
-
-
-
-
-
-
-
-
-
 
 
 
+#[derive(Copy, Clone)]
 #[repr(transparent)]
-pub struct Input (km_sys::INPUT);
+pub struct InputSys(km_sys::INPUT);
 
-impl Input {
+impl InputSys {
     /// Safety: Must be a valid INPUT struct
     pub unsafe fn new(inner: km_sys::INPUT) -> Self {
         Self(inner)
@@ -344,23 +245,46 @@ impl Input {
     }
 }
 
+impl TryFrom<Input> for InputSys {
+    type Error = crate::Error;
+
+    fn try_from(rusty: Input) -> crate::Result<Self> {
+        Ok(match rusty {
+            Input::Mouse(m) => Self::from_mouse(&m.into()),
+            Input::Keyboard(k) => Self::from_keyboard(&k.try_into()?),
+            Input::Hardware(h) => Self::from_hardware(&h.into()),
+        })
+    }
+}
+
 /// Safe interface to windows SendInput function. [MS Docs](https://docs.microsoft.com/en-us/windows/win32/api/km_sys/nf-km_sys-sendinput)
-/// 
-/// Panics:
-/// 
-/// Panics if the length of the input slice >= i32::MAX
-pub fn send_input(inputs: &[Input]) -> io::Result<u32> {
-    if inputs.len() >= i32::MAX as usize { panic!() }
-    if inputs.len() == 0 { return Ok(0) }
+///
+/// Fails with `Error::VirtualKeyOutOfRange` before sending anything if any input has a bad virtual key. If nothing was accepted, fails with the OS error, or with `Error::BlockedByUipi` if there isn't one.
+pub fn send_input(inputs: &[Input]) -> crate::Result<u32> {
+    let inputs = inputs.iter().map(|i| InputSys::try_from(*i)).collect::<crate::Result<Vec<InputSys>>>()?;
+    send_input_sys(&inputs)
+}
+
+/// Like `send_input`, but for inputs that have already been converted to `INPUT` structs.
+pub fn send_input_sys(inputs: &[InputSys]) -> crate::Result<u32> {
+    if inputs.len() >= i32::MAX as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Too many inputs for one SendInput call").into());
+    }
+    if inputs.is_empty() { return Ok(0) }
     let res = unsafe {
         km_sys::SendInput(
             std::mem::transmute(inputs),
             std::mem::size_of::<km_sys::INPUT>().try_into().unwrap(),
         )
     };
-    if res == 0 {
-        Err(io::Error::last_os_error())
+    if res != 0 {
+        return Ok(res);
+    }
+    // "This function fails when it is blocked by UIPI. Note that neither GetLastError nor the return value will indicate the failure was caused by UIPI blocking." So with no error code, UIPI is by far the most likely reason, and anything else, such as access being denied on the secure desktop, comes with its own code
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() == Some(0) {
+        Err(crate::Error::BlockedByUipi)
     } else {
-        Ok(res)
+        Err(e.into())
     }
 }
\ No newline at end of file
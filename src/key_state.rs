@@ -0,0 +1,73 @@
+//! Where the planner finds out which keys are already held or toggled on, such as Caps Lock.
+
+use std::collections::HashSet;
+
+/// Something that knows which keys are down and which toggle keys (Caps Lock, Num Lock, Scroll Lock) are on.
+pub trait KeyStateSource {
+    fn is_toggled(&self, vk: u8) -> bool;
+    fn is_down(&self, vk: u8) -> bool;
+}
+
+impl<K: KeyStateSource + ?Sized> KeyStateSource for &K {
+    fn is_toggled(&self, vk: u8) -> bool {
+        (**self).is_toggled(vk)
+    }
+
+    fn is_down(&self, vk: u8) -> bool {
+        (**self).is_down(vk)
+    }
+}
+
+/// A fixed snapshot of the keyboard state. The default has nothing down and nothing toggled, which is also what tests can start from to fake a state.
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct KeyState {
+    pub toggled: HashSet<u8>,
+    pub down: HashSet<u8>,
+}
+
+impl KeyState {
+    pub fn with_toggled(mut self, vk: u8) -> Self {
+        self.toggled.insert(vk);
+        self
+    }
+
+    pub fn with_down(mut self, vk: u8) -> Self {
+        self.down.insert(vk);
+        self
+    }
+
+    /// The state of the keyboard right now, according to GetKeyState and GetAsyncKeyState.
+    #[cfg(windows)]
+    pub fn current() -> Self {
+        use windows::Win32::UI::Input::KeyboardAndMouse as km_sys;
+
+        let mut state = Self::default();
+        for vk in 1..=254u8 {
+            // "If the low-order bit is 1, the key is toggled."
+            if unsafe { km_sys::GetKeyState(vk.into()) } & 1 != 0 {
+                state.toggled.insert(vk);
+            }
+            // "If the most significant bit is set, the key is down"
+            if unsafe { km_sys::GetAsyncKeyState(vk.into()) } < 0 {
+                state.down.insert(vk);
+            }
+        }
+        state
+    }
+
+    /// There's no portable way to ask outside of windows, so this is nothing down and nothing toggled.
+    #[cfg(not(windows))]
+    pub fn current() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyStateSource for KeyState {
+    fn is_toggled(&self, vk: u8) -> bool {
+        self.toggled.contains(&vk)
+    }
+
+    fn is_down(&self, vk: u8) -> bool {
+        self.down.contains(&vk)
+    }
+}
@@ -0,0 +1,476 @@
+//! Built-in copies of some common windows layouts, for planning keystrokes where there's no windows API to ask, or for layouts that aren't installed.
+
+use super::*;
+
+/// Keys that do the same thing on every bundled layout.
+/// (scan code, virtual key, unshifted text, shifted text)
+const COMMON_KEYS:&[(u8, u8, &str, &str)] = &[
+    (0x01, VK_ESCAPE,     "\x1b", "\x1b"),
+    (0x0E, VK_BACK,       "\x08", "\x08"),
+    (0x0F, VK_TAB,        "\t",   "\t"),
+    (0x1C, VK_RETURN,     "\r",   "\r"),
+    (0x1D, VK_LCONTROL,   "",     ""),
+    (0x2A, VK_LSHIFT,     "",     ""),
+    (0x36, VK_RSHIFT,     "",     ""),
+    (0x37, VK_MULTIPLY,   "*",    "*"),
+    (0x38, VK_LMENU,      "",     ""),
+    (0x39, VK_SPACE,      " ",    " "),
+    (0x3A, VK_CAPITAL,    "",     ""),
+    (0x3B, VK_F1,         "",     ""),
+    (0x3C, VK_F2,         "",     ""),
+    (0x3D, VK_F3,         "",     ""),
+    (0x3E, VK_F4,         "",     ""),
+    (0x3F, VK_F5,         "",     ""),
+    (0x40, VK_F6,         "",     ""),
+    (0x41, VK_F7,         "",     ""),
+    (0x42, VK_F8,         "",     ""),
+    (0x43, VK_F9,         "",     ""),
+    (0x44, VK_F10,        "",     ""),
+    (0x45, VK_NUMLOCK,    "",     ""),
+    (0x46, VK_SCROLL,     "",     ""),
+    (0x47, VK_HOME,       "",     ""),
+    (0x48, VK_UP,         "",     ""),
+    (0x49, VK_PRIOR,      "",     ""),
+    (0x4A, VK_SUBTRACT,   "-",    "-"),
+    (0x4B, VK_LEFT,       "",     ""),
+    (0x4C, VK_CLEAR,      "",     ""),
+    (0x4D, VK_RIGHT,      "",     ""),
+    (0x4E, VK_ADD,        "+",    "+"),
+    (0x4F, VK_END,        "",     ""),
+    (0x50, VK_DOWN,       "",     ""),
+    (0x51, VK_NEXT,       "",     ""),
+    (0x52, VK_INSERT,     "",     ""),
+    (0x53, VK_DELETE,     "",     ""),
+    (0x57, VK_F11,        "",     ""),
+    (0x58, VK_F12,        "",     ""),
+];
+
+/// The keys that differ between layouts.
+/// (scan code, virtual key, unshifted text, shifted text, AltGr text)
+type CharKeys = &'static [(u8, u8, &'static str, &'static str, &'static str)];
+
+/// (scan code, shift state, the character the dead key produces when followed by a space)
+type DeadKeys = &'static [(u8, u8, char)];
+
+/// What the dead keys of the bundled layouts combine into.
+/// (dead key's character, base characters, the composed characters in the same order)
+const COMPOSITIONS:&[(char, &str, &str)] = &[
+    ('^', "aeiouAEIOU",   "âêîôûÂÊÎÔÛ"),
+    ('´', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+    ('`', "aeiouAEIOU",   "àèìòùÀÈÌÒÙ"),
+    ('~', "aonAON",       "ãõñÃÕÑ"),
+    ('¨', "aeiouyAEIOU",  "äëïöüÿÄËÏÖÜ"),
+];
+
+const US_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "1",    "!",  ""),
+    (0x03, VK_DIGIT2,     "2",    "@",  ""),
+    (0x04, VK_DIGIT3,     "3",    "#",  ""),
+    (0x05, VK_DIGIT4,     "4",    "$",  ""),
+    (0x06, VK_DIGIT5,     "5",    "%",  ""),
+    (0x07, VK_DIGIT6,     "6",    "^",  ""),
+    (0x08, VK_DIGIT7,     "7",    "&",  ""),
+    (0x09, VK_DIGIT8,     "8",    "*",  ""),
+    (0x0A, VK_DIGIT9,     "9",    "(",  ""),
+    (0x0B, VK_DIGIT0,     "0",    ")",  ""),
+    (0x0C, VK_OEM_MINUS,  "-",    "_",  ""),
+    (0x0D, VK_OEM_PLUS,   "=",    "+",  ""),
+    (0x10, VK_Q,          "q",    "Q",  ""),
+    (0x11, VK_W,          "w",    "W",  ""),
+    (0x12, VK_E,          "e",    "E",  ""),
+    (0x13, VK_R,          "r",    "R",  ""),
+    (0x14, VK_T,          "t",    "T",  ""),
+    (0x15, VK_Y,          "y",    "Y",  ""),
+    (0x16, VK_U,          "u",    "U",  ""),
+    (0x17, VK_I,          "i",    "I",  ""),
+    (0x18, VK_O,          "o",    "O",  ""),
+    (0x19, VK_P,          "p",    "P",  ""),
+    (0x1A, VK_OEM_4,      "[",    "{",  ""),
+    (0x1B, VK_OEM_6,      "]",    "}",  ""),
+    (0x1E, VK_A,          "a",    "A",  ""),
+    (0x1F, VK_S,          "s",    "S",  ""),
+    (0x20, VK_D,          "d",    "D",  ""),
+    (0x21, VK_F,          "f",    "F",  ""),
+    (0x22, VK_G,          "g",    "G",  ""),
+    (0x23, VK_H,          "h",    "H",  ""),
+    (0x24, VK_J,          "j",    "J",  ""),
+    (0x25, VK_K,          "k",    "K",  ""),
+    (0x26, VK_L,          "l",    "L",  ""),
+    (0x27, VK_OEM_1,      ";",    ":",  ""),
+    (0x28, VK_OEM_7,      "'",    "\"", ""),
+    (0x29, VK_OEM_3,      "`",    "~",  ""),
+    (0x2B, VK_OEM_5,      "\\",   "|",  ""),
+    (0x2C, VK_Z,          "z",    "Z",  ""),
+    (0x2D, VK_X,          "x",    "X",  ""),
+    (0x2E, VK_C,          "c",    "C",  ""),
+    (0x2F, VK_V,          "v",    "V",  ""),
+    (0x30, VK_B,          "b",    "B",  ""),
+    (0x31, VK_N,          "n",    "N",  ""),
+    (0x32, VK_M,          "m",    "M",  ""),
+    (0x33, VK_OEM_COMMA,  ",",    "<",  ""),
+    (0x34, VK_OEM_PERIOD, ".",    ">",  ""),
+    (0x35, VK_OEM_2,      "/",    "?",  ""),
+    (0x56, VK_OEM_102,    "\\",   "|",  ""),
+];
+
+const UK_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "1",    "!",  ""),
+    (0x03, VK_DIGIT2,     "2",    "\"", ""),
+    (0x04, VK_DIGIT3,     "3",    "£",  ""),
+    (0x05, VK_DIGIT4,     "4",    "$",  "€"),
+    (0x06, VK_DIGIT5,     "5",    "%",  ""),
+    (0x07, VK_DIGIT6,     "6",    "^",  ""),
+    (0x08, VK_DIGIT7,     "7",    "&",  ""),
+    (0x09, VK_DIGIT8,     "8",    "*",  ""),
+    (0x0A, VK_DIGIT9,     "9",    "(",  ""),
+    (0x0B, VK_DIGIT0,     "0",    ")",  ""),
+    (0x0C, VK_OEM_MINUS,  "-",    "_",  ""),
+    (0x0D, VK_OEM_PLUS,   "=",    "+",  ""),
+    (0x10, VK_Q,          "q",    "Q",  ""),
+    (0x11, VK_W,          "w",    "W",  ""),
+    (0x12, VK_E,          "e",    "E",  "é"),
+    (0x13, VK_R,          "r",    "R",  ""),
+    (0x14, VK_T,          "t",    "T",  ""),
+    (0x15, VK_Y,          "y",    "Y",  ""),
+    (0x16, VK_U,          "u",    "U",  "ú"),
+    (0x17, VK_I,          "i",    "I",  "í"),
+    (0x18, VK_O,          "o",    "O",  "ó"),
+    (0x19, VK_P,          "p",    "P",  ""),
+    (0x1A, VK_OEM_4,      "[",    "{",  ""),
+    (0x1B, VK_OEM_6,      "]",    "}",  ""),
+    (0x1E, VK_A,          "a",    "A",  "á"),
+    (0x1F, VK_S,          "s",    "S",  ""),
+    (0x20, VK_D,          "d",    "D",  ""),
+    (0x21, VK_F,          "f",    "F",  ""),
+    (0x22, VK_G,          "g",    "G",  ""),
+    (0x23, VK_H,          "h",    "H",  ""),
+    (0x24, VK_J,          "j",    "J",  ""),
+    (0x25, VK_K,          "k",    "K",  ""),
+    (0x26, VK_L,          "l",    "L",  ""),
+    (0x27, VK_OEM_1,      ";",    ":",  ""),
+    (0x28, VK_OEM_3,      "'",    "@",  ""),
+    (0x29, VK_OEM_8,      "`",    "¬",  "¦"),
+    (0x2B, VK_OEM_7,      "#",    "~",  ""),
+    (0x2C, VK_Z,          "z",    "Z",  ""),
+    (0x2D, VK_X,          "x",    "X",  ""),
+    (0x2E, VK_C,          "c",    "C",  ""),
+    (0x2F, VK_V,          "v",    "V",  ""),
+    (0x30, VK_B,          "b",    "B",  ""),
+    (0x31, VK_N,          "n",    "N",  ""),
+    (0x32, VK_M,          "m",    "M",  ""),
+    (0x33, VK_OEM_COMMA,  ",",    "<",  ""),
+    (0x34, VK_OEM_PERIOD, ".",    ">",  ""),
+    (0x35, VK_OEM_2,      "/",    "?",  ""),
+    (0x56, VK_OEM_5,      "\\",   "|",  ""),
+];
+
+const GERMAN_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "1",    "!",  ""),
+    (0x03, VK_DIGIT2,     "2",    "\"", "²"),
+    (0x04, VK_DIGIT3,     "3",    "§",  "³"),
+    (0x05, VK_DIGIT4,     "4",    "$",  ""),
+    (0x06, VK_DIGIT5,     "5",    "%",  ""),
+    (0x07, VK_DIGIT6,     "6",    "&",  ""),
+    (0x08, VK_DIGIT7,     "7",    "/",  "{"),
+    (0x09, VK_DIGIT8,     "8",    "(",  "["),
+    (0x0A, VK_DIGIT9,     "9",    ")",  "]"),
+    (0x0B, VK_DIGIT0,     "0",    "=",  "}"),
+    (0x0C, VK_OEM_4,      "ß",    "?",  "\\"),
+    (0x0D, VK_OEM_6,      "",     "",   ""),
+    (0x10, VK_Q,          "q",    "Q",  "@"),
+    (0x11, VK_W,          "w",    "W",  ""),
+    (0x12, VK_E,          "e",    "E",  "€"),
+    (0x13, VK_R,          "r",    "R",  ""),
+    (0x14, VK_T,          "t",    "T",  ""),
+    (0x15, VK_Z,          "z",    "Z",  ""),
+    (0x16, VK_U,          "u",    "U",  ""),
+    (0x17, VK_I,          "i",    "I",  ""),
+    (0x18, VK_O,          "o",    "O",  ""),
+    (0x19, VK_P,          "p",    "P",  ""),
+    (0x1A, VK_OEM_1,      "ü",    "Ü",  ""),
+    (0x1B, VK_OEM_PLUS,   "+",    "*",  "~"),
+    (0x1E, VK_A,          "a",    "A",  ""),
+    (0x1F, VK_S,          "s",    "S",  ""),
+    (0x20, VK_D,          "d",    "D",  ""),
+    (0x21, VK_F,          "f",    "F",  ""),
+    (0x22, VK_G,          "g",    "G",  ""),
+    (0x23, VK_H,          "h",    "H",  ""),
+    (0x24, VK_J,          "j",    "J",  ""),
+    (0x25, VK_K,          "k",    "K",  ""),
+    (0x26, VK_L,          "l",    "L",  ""),
+    (0x27, VK_OEM_3,      "ö",    "Ö",  ""),
+    (0x28, VK_OEM_7,      "ä",    "Ä",  ""),
+    (0x29, VK_OEM_5,      "",     "°",  ""),
+    (0x2B, VK_OEM_2,      "#",    "'",  ""),
+    (0x2C, VK_Y,          "y",    "Y",  ""),
+    (0x2D, VK_X,          "x",    "X",  ""),
+    (0x2E, VK_C,          "c",    "C",  ""),
+    (0x2F, VK_V,          "v",    "V",  ""),
+    (0x30, VK_B,          "b",    "B",  ""),
+    (0x31, VK_N,          "n",    "N",  ""),
+    (0x32, VK_M,          "m",    "M",  "µ"),
+    (0x33, VK_OEM_COMMA,  ",",    ";",  ""),
+    (0x34, VK_OEM_PERIOD, ".",    ":",  ""),
+    (0x35, VK_OEM_MINUS,  "-",    "_",  ""),
+    (0x56, VK_OEM_102,    "<",    ">",  "|"),
+];
+
+const GERMAN_DEAD_KEYS:DeadKeys = &[
+    (0x0D, 0,                 '´'),
+    (0x0D, SHIFT_STATE_SHIFT, '`'),
+    (0x29, 0,                 '^'),
+];
+
+const FRENCH_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "&",    "1",  ""),
+    (0x03, VK_DIGIT2,     "é",    "2",  ""),
+    (0x04, VK_DIGIT3,     "\"",   "3",  "#"),
+    (0x05, VK_DIGIT4,     "'",    "4",  "{"),
+    (0x06, VK_DIGIT5,     "(",    "5",  "["),
+    (0x07, VK_DIGIT6,     "-",    "6",  "|"),
+    (0x08, VK_DIGIT7,     "è",    "7",  ""),
+    (0x09, VK_DIGIT8,     "_",    "8",  "\\"),
+    (0x0A, VK_DIGIT9,     "ç",    "9",  "^"),
+    (0x0B, VK_DIGIT0,     "à",    "0",  "@"),
+    (0x0C, VK_OEM_4,      ")",    "°",  "]"),
+    (0x0D, VK_OEM_PLUS,   "=",    "+",  "}"),
+    (0x10, VK_A,          "a",    "A",  ""),
+    (0x11, VK_Z,          "z",    "Z",  ""),
+    (0x12, VK_E,          "e",    "E",  "€"),
+    (0x13, VK_R,          "r",    "R",  ""),
+    (0x14, VK_T,          "t",    "T",  ""),
+    (0x15, VK_Y,          "y",    "Y",  ""),
+    (0x16, VK_U,          "u",    "U",  ""),
+    (0x17, VK_I,          "i",    "I",  ""),
+    (0x18, VK_O,          "o",    "O",  ""),
+    (0x19, VK_P,          "p",    "P",  ""),
+    (0x1A, VK_OEM_6,      "",     "",   ""),
+    (0x1B, VK_OEM_1,      "$",    "£",  "¤"),
+    (0x1E, VK_Q,          "q",    "Q",  ""),
+    (0x1F, VK_S,          "s",    "S",  ""),
+    (0x20, VK_D,          "d",    "D",  ""),
+    (0x21, VK_F,          "f",    "F",  ""),
+    (0x22, VK_G,          "g",    "G",  ""),
+    (0x23, VK_H,          "h",    "H",  ""),
+    (0x24, VK_J,          "j",    "J",  ""),
+    (0x25, VK_K,          "k",    "K",  ""),
+    (0x26, VK_L,          "l",    "L",  ""),
+    (0x27, VK_M,          "m",    "M",  ""),
+    (0x28, VK_OEM_3,      "ù",    "%",  ""),
+    (0x29, VK_OEM_7,      "²",    "",   ""),
+    (0x2B, VK_OEM_5,      "*",    "µ",  ""),
+    (0x2C, VK_W,          "w",    "W",  ""),
+    (0x2D, VK_X,          "x",    "X",  ""),
+    (0x2E, VK_C,          "c",    "C",  ""),
+    (0x2F, VK_V,          "v",    "V",  ""),
+    (0x30, VK_B,          "b",    "B",  ""),
+    (0x31, VK_N,          "n",    "N",  ""),
+    (0x32, VK_OEM_COMMA,  ",",    "?",  ""),
+    (0x33, VK_OEM_PERIOD, ";",    ".",  ""),
+    (0x34, VK_OEM_2,      ":",    "/",  ""),
+    (0x35, VK_OEM_8,      "!",    "§",  ""),
+    (0x56, VK_OEM_102,    "<",    ">",  ""),
+];
+
+const FRENCH_DEAD_KEYS:DeadKeys = &[
+    (0x03, SHIFT_STATE_ALTGR, '~'),
+    (0x08, SHIFT_STATE_ALTGR, '`'),
+    (0x1A, 0,                 '^'),
+    (0x1A, SHIFT_STATE_SHIFT, '¨'),
+];
+
+const DVORAK_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "1",    "!",  ""),
+    (0x03, VK_DIGIT2,     "2",    "@",  ""),
+    (0x04, VK_DIGIT3,     "3",    "#",  ""),
+    (0x05, VK_DIGIT4,     "4",    "$",  ""),
+    (0x06, VK_DIGIT5,     "5",    "%",  ""),
+    (0x07, VK_DIGIT6,     "6",    "^",  ""),
+    (0x08, VK_DIGIT7,     "7",    "&",  ""),
+    (0x09, VK_DIGIT8,     "8",    "*",  ""),
+    (0x0A, VK_DIGIT9,     "9",    "(",  ""),
+    (0x0B, VK_DIGIT0,     "0",    ")",  ""),
+    (0x0C, VK_OEM_4,      "[",    "{",  ""),
+    (0x0D, VK_OEM_6,      "]",    "}",  ""),
+    (0x10, VK_OEM_7,      "'",    "\"", ""),
+    (0x11, VK_OEM_COMMA,  ",",    "<",  ""),
+    (0x12, VK_OEM_PERIOD, ".",    ">",  ""),
+    (0x13, VK_P,          "p",    "P",  ""),
+    (0x14, VK_Y,          "y",    "Y",  ""),
+    (0x15, VK_F,          "f",    "F",  ""),
+    (0x16, VK_G,          "g",    "G",  ""),
+    (0x17, VK_C,          "c",    "C",  ""),
+    (0x18, VK_R,          "r",    "R",  ""),
+    (0x19, VK_L,          "l",    "L",  ""),
+    (0x1A, VK_OEM_2,      "/",    "?",  ""),
+    (0x1B, VK_OEM_PLUS,   "=",    "+",  ""),
+    (0x1E, VK_A,          "a",    "A",  ""),
+    (0x1F, VK_O,          "o",    "O",  ""),
+    (0x20, VK_E,          "e",    "E",  ""),
+    (0x21, VK_U,          "u",    "U",  ""),
+    (0x22, VK_I,          "i",    "I",  ""),
+    (0x23, VK_D,          "d",    "D",  ""),
+    (0x24, VK_H,          "h",    "H",  ""),
+    (0x25, VK_T,          "t",    "T",  ""),
+    (0x26, VK_N,          "n",    "N",  ""),
+    (0x27, VK_S,          "s",    "S",  ""),
+    (0x28, VK_OEM_MINUS,  "-",    "_",  ""),
+    (0x29, VK_OEM_3,      "`",    "~",  ""),
+    (0x2B, VK_OEM_5,      "\\",   "|",  ""),
+    (0x2C, VK_OEM_1,      ";",    ":",  ""),
+    (0x2D, VK_Q,          "q",    "Q",  ""),
+    (0x2E, VK_J,          "j",    "J",  ""),
+    (0x2F, VK_K,          "k",    "K",  ""),
+    (0x30, VK_X,          "x",    "X",  ""),
+    (0x31, VK_B,          "b",    "B",  ""),
+    (0x32, VK_M,          "m",    "M",  ""),
+    (0x33, VK_W,          "w",    "W",  ""),
+    (0x34, VK_V,          "v",    "V",  ""),
+    (0x35, VK_Z,          "z",    "Z",  ""),
+    (0x56, VK_OEM_102,    "\\",   "|",  ""),
+];
+
+const RUSSIAN_KEYS:CharKeys = &[
+    (0x02, VK_DIGIT1,     "1",    "!",  ""),
+    (0x03, VK_DIGIT2,     "2",    "\"", ""),
+    (0x04, VK_DIGIT3,     "3",    "№",  ""),
+    (0x05, VK_DIGIT4,     "4",    ";",  ""),
+    (0x06, VK_DIGIT5,     "5",    "%",  ""),
+    (0x07, VK_DIGIT6,     "6",    ":",  ""),
+    (0x08, VK_DIGIT7,     "7",    "?",  ""),
+    (0x09, VK_DIGIT8,     "8",    "*",  ""),
+    (0x0A, VK_DIGIT9,     "9",    "(",  ""),
+    (0x0B, VK_DIGIT0,     "0",    ")",  ""),
+    (0x0C, VK_OEM_MINUS,  "-",    "_",  ""),
+    (0x0D, VK_OEM_PLUS,   "=",    "+",  ""),
+    (0x10, VK_Q,          "й",    "Й",  ""),
+    (0x11, VK_W,          "ц",    "Ц",  ""),
+    (0x12, VK_E,          "у",    "У",  ""),
+    (0x13, VK_R,          "к",    "К",  ""),
+    (0x14, VK_T,          "е",    "Е",  ""),
+    (0x15, VK_Y,          "н",    "Н",  ""),
+    (0x16, VK_U,          "г",    "Г",  ""),
+    (0x17, VK_I,          "ш",    "Ш",  ""),
+    (0x18, VK_O,          "щ",    "Щ",  ""),
+    (0x19, VK_P,          "з",    "З",  ""),
+    (0x1A, VK_OEM_4,      "х",    "Х",  ""),
+    (0x1B, VK_OEM_6,      "ъ",    "Ъ",  ""),
+    (0x1E, VK_A,          "ф",    "Ф",  ""),
+    (0x1F, VK_S,          "ы",    "Ы",  ""),
+    (0x20, VK_D,          "в",    "В",  ""),
+    (0x21, VK_F,          "а",    "А",  ""),
+    (0x22, VK_G,          "п",    "П",  ""),
+    (0x23, VK_H,          "р",    "Р",  ""),
+    (0x24, VK_J,          "о",    "О",  ""),
+    (0x25, VK_K,          "л",    "Л",  ""),
+    (0x26, VK_L,          "д",    "Д",  ""),
+    (0x27, VK_OEM_1,      "ж",    "Ж",  ""),
+    (0x28, VK_OEM_7,      "э",    "Э",  ""),
+    (0x29, VK_OEM_3,      "ё",    "Ё",  ""),
+    (0x2B, VK_OEM_5,      "\\",   "/",  ""),
+    (0x2C, VK_Z,          "я",    "Я",  ""),
+    (0x2D, VK_X,          "ч",    "Ч",  ""),
+    (0x2E, VK_C,          "с",    "С",  ""),
+    (0x2F, VK_V,          "м",    "М",  ""),
+    (0x30, VK_B,          "и",    "И",  ""),
+    (0x31, VK_N,          "т",    "Т",  ""),
+    (0x32, VK_M,          "ь",    "Ь",  ""),
+    (0x33, VK_OEM_COMMA,  "б",    "Б",  ""),
+    (0x34, VK_OEM_PERIOD, "ю",    "Ю",  ""),
+    (0x35, VK_OEM_2,      ".",    ",",  ""),
+    (0x56, VK_OEM_102,    "\\",   "/",  ""),
+];
+
+/// One of the layouts that ship with the crate.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct BundledLayout {
+    /// The keyboard layout identifier as windows writes it, eg "00000407" for German
+    pub klid: &'static str,
+    /// The name windows shows for the layout
+    pub name: &'static str,
+    /// What GetKeyboardLayout returns while the layout is in use
+    pub id: HKL,
+    keys: CharKeys,
+    dead_keys: DeadKeys,
+}
+
+pub const US_KLID:&str = "00000409";
+
+pub const BUNDLED_LAYOUTS:&[BundledLayout] = &[
+    BundledLayout{klid: US_KLID,    name: "US",                   id: HKL(0x04090409),   keys: US_KEYS,      dead_keys: &[]},
+    BundledLayout{klid: "00000809", name: "United Kingdom",       id: HKL(0x08090809),   keys: UK_KEYS,      dead_keys: &[]},
+    BundledLayout{klid: "00000407", name: "German",               id: HKL(0x04070407),   keys: GERMAN_KEYS,  dead_keys: GERMAN_DEAD_KEYS},
+    BundledLayout{klid: "0000040C", name: "French",               id: HKL(0x040C040C),   keys: FRENCH_KEYS,  dead_keys: FRENCH_DEAD_KEYS},
+    // Layout variants get a special device handle. HKLs are sign extended on 64-bit windows.
+    BundledLayout{klid: "00010409", name: "United States-Dvorak", id: HKL(0xF0020409u32 as i32 as isize), keys: DVORAK_KEYS, dead_keys: &[]},
+    BundledLayout{klid: "00000419", name: "Russian",              id: HKL(0x04190419),   keys: RUSSIAN_KEYS, dead_keys: &[]},
+];
+
+impl BundledLayout {
+    /// Looks up a bundled layout by KLID. Case doesn't matter, so "0000040c" finds French.
+    pub fn by_klid(klid: &str) -> Option<&'static Self> {
+        BUNDLED_LAYOUTS.iter().find(|l| l.klid.eq_ignore_ascii_case(klid))
+    }
+
+    /// Looks up a bundled layout by the handle windows uses for it while it's loaded.
+    pub fn by_id(id: HKL) -> Option<&'static Self> {
+        BUNDLED_LAYOUTS.iter().find(|l| l.id == id)
+    }
+
+    pub fn description(&self) -> LayoutDescription {
+        let mut desc = LayoutDescription::new(self.id);
+        let common = COMMON_KEYS.iter().map(|(sc, vk, base, shifted)| (sc, vk, base, shifted, &""));
+        let chars = self.keys.iter().map(|(sc, vk, base, shifted, altgr)| (sc, vk, base, shifted, altgr));
+        for (sc, vk, base, shifted, altgr) in common.chain(chars) {
+            let mut key = KeyDescription::new(*sc, *vk);
+            for (ss, text) in [(0, base), (SHIFT_STATE_SHIFT, shifted), (SHIFT_STATE_ALTGR, altgr)] {
+                if !text.is_empty() {
+                    key = key.text(ss, *text);
+                }
+            }
+            // Caps Lock affects every key whose shifted text is the uppercase of its unshifted text, which is the letters including ä, ё etc
+            let is_letter = base.chars().all(char::is_lowercase) && !base.is_empty() && base.to_uppercase() == **shifted;
+            if is_letter {
+                key = key.caps_lock();
+            }
+            for (_, ss, c) in self.dead_keys.iter().filter(|(dead_sc, _, _)| dead_sc == sc) {
+                key = key.dead(*ss, *c);
+            }
+            desc.keys.push(key);
+        }
+        desc.keys.sort_by_key(|k| k.sc);
+        for (_, _, dead) in self.dead_keys {
+            // Dead key followed by space types the dead key's character by itself
+            desc = desc.compose(*dead, ' ', *dead);
+            for (accent, bases, composed) in COMPOSITIONS.iter().filter(|(accent, _, _)| accent == dead) {
+                for (base, c) in bases.chars().zip(composed.chars()) {
+                    desc = desc.compose(*accent, base, c);
+                }
+            }
+        }
+        desc
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        KeyboardLayout::from_description(&self.description())
+    }
+}
+
+impl LayoutDescription {
+    /// The windows "US" layout.
+    pub fn us() -> Self {
+        BundledLayout::by_klid(US_KLID).unwrap().description()
+    }
+}
+
+impl KeyboardLayout {
+    /// The windows "US" layout, without asking the OS.
+    pub fn us() -> Self {
+        Self::from_description(&LayoutDescription::us())
+    }
+
+    /// One of the `BUNDLED_LAYOUTS`, without asking the OS. Returns None if there's no bundled layout with that KLID.
+    pub fn bundled(klid: &str) -> Option<Self> {
+        BundledLayout::by_klid(klid).map(BundledLayout::layout)
+    }
+}
@@ -0,0 +1,103 @@
+//! Single byte code pages, for typing characters as Alt+Numpad codes.
+
+/// A single byte code page. The lower half is always ASCII, so only the upper half is stored.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct CodePage {
+    pub id: u32,
+    high: [char; 128],
+}
+
+impl CodePage {
+    /// `high` is what the bytes 0x80 to 0xFF decode to.
+    pub const fn new(id: u32, high: [char; 128]) -> Self {
+        Self{id, high}
+    }
+
+    pub fn decode(&self, b: u8) -> char {
+        if b < 0x80 {
+            b.into()
+        } else {
+            self.high[usize::from(b - 0x80)]
+        }
+    }
+
+    pub fn encode(&self, c: char) -> Option<u8> {
+        if c.is_ascii() {
+            return Some(c as u8);
+        }
+        if c == char::REPLACEMENT_CHARACTER {
+            return None;
+        }
+        self.high.iter().position(|h| *h == c).map(|i| 0x80 + i as u8)
+    }
+
+    /// The OEM code page, which Alt+Numpad codes without a leading zero use.
+    #[cfg(windows)]
+    pub fn oem() -> &'static CodePage {
+        &SYSTEM_OEM
+    }
+
+    /// The ANSI code page, which Alt+Numpad codes with a leading zero use.
+    #[cfg(windows)]
+    pub fn ansi() -> &'static CodePage {
+        &SYSTEM_ANSI
+    }
+
+    /// What a US English windows uses, since there's no system code page to ask for.
+    #[cfg(not(windows))]
+    pub fn oem() -> &'static CodePage {
+        &CP437
+    }
+
+    /// What a US English windows uses, since there's no system code page to ask for.
+    #[cfg(not(windows))]
+    pub fn ansi() -> &'static CodePage {
+        &CP1252
+    }
+
+    /// Asks windows what each byte of the code page decodes to. Bytes that don't decode on their own, such as lead bytes of double byte code pages, become U+FFFD and are never used.
+    #[cfg(windows)]
+    fn system(id: u32) -> Self {
+        use windows::Win32::Globalization::MultiByteToWideChar;
+
+        let mut high = [char::REPLACEMENT_CHARACTER; 128];
+        for (i, c) in high.iter_mut().enumerate() {
+            let mut wide = [0u16; 2];
+            let len = unsafe { MultiByteToWideChar(id, Default::default(), &[0x80 + i as u8], &mut wide) };
+            if len == 1 {
+                *c = char::from_u32(wide[0].into()).unwrap_or(char::REPLACEMENT_CHARACTER);
+            }
+        }
+        Self{id, high}
+    }
+}
+
+#[cfg(windows)]
+lazy_static::lazy_static! {
+    static ref SYSTEM_OEM: CodePage = CodePage::system(unsafe { windows::Win32::Globalization::GetOEMCP() });
+    static ref SYSTEM_ANSI: CodePage = CodePage::system(unsafe { windows::Win32::Globalization::GetACP() });
+}
+
+/// The OEM code page of US English windows
+pub static CP437: CodePage = CodePage::new(437, [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{00A0}',
+]);
+
+/// The ANSI code page of Western European windows. The five unused bytes decode to the matching C1 control characters, like MultiByteToWideChar does.
+pub static CP1252: CodePage = CodePage::new(1252, [
+    '€', '\u{0081}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{008D}', 'Ž', '\u{008F}',
+    '\u{0090}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{009D}', 'ž', 'Ÿ',
+    '\u{00A0}', '¡', '¢', '£', '¤', '¥', '¦', '§', '¨', '©', 'ª', '«', '¬', '\u{00AD}', '®', '¯',
+    '°', '±', '²', '³', '´', 'µ', '¶', '·', '¸', '¹', 'º', '»', '¼', '½', '¾', '¿',
+    'À', 'Á', 'Â', 'Ã', 'Ä', 'Å', 'Æ', 'Ç', 'È', 'É', 'Ê', 'Ë', 'Ì', 'Í', 'Î', 'Ï',
+    'Ð', 'Ñ', 'Ò', 'Ó', 'Ô', 'Õ', 'Ö', '×', 'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'Ý', 'Þ', 'ß',
+    'à', 'á', 'â', 'ã', 'ä', 'å', 'æ', 'ç', 'è', 'é', 'ê', 'ë', 'ì', 'í', 'î', 'ï',
+    'ð', 'ñ', 'ò', 'ó', 'ô', 'õ', 'ö', '÷', 'ø', 'ù', 'ú', 'û', 'ü', 'ý', 'þ', 'ÿ',
+]);
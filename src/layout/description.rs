@@ -0,0 +1,145 @@
+//! Describing a layout as plain data, so that it can be built without asking windows.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// What pressing a key produces in one shift state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOutput {
+    /// The key types this text, usually a single character.
+    Text(String),
+    /// The key is a dead key. The char is what it produces when followed by a space, eg '^' for a circumflex dead key.
+    Dead(char),
+}
+
+/// One physical key of a `LayoutDescription`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyDescription {
+    /// Scan code (set 1, without the E0 prefix)
+    pub sc: u8,
+    /// The virtual key that the scan code maps to in this layout
+    pub vk: u8,
+    /// What the key produces, keyed by shift state (a combination of the `SHIFT_STATE_*` bits). Shift states that produce nothing are left out.
+    pub outputs: BTreeMap<u8, KeyOutput>,
+    /// Whether Caps Lock swaps the unshifted and shifted text, like it does for letters (CAPLOK)
+    pub caps_lock: bool,
+    /// Text the key types while Caps Lock is on, for keys where that isn't just the other shift state (SGCAPS). Keyed by shift state.
+    pub caps_outputs: BTreeMap<u8, String>,
+}
+
+impl KeyDescription {
+    pub fn new(sc: u8, vk: u8) -> Self {
+        Self{
+            sc,
+            vk,
+            outputs: BTreeMap::new(),
+            caps_lock: false,
+            caps_outputs: BTreeMap::new(),
+        }
+    }
+
+    /// Makes Caps Lock swap the unshifted and shifted text of the key.
+    pub fn caps_lock(mut self) -> Self {
+        self.caps_lock = true;
+        self
+    }
+
+    /// Sets the text that the key types in shift state `ss` while Caps Lock is on.
+    pub fn caps_text(mut self, ss: u8, text: impl Into<String>) -> Self {
+        self.caps_outputs.insert(ss, text.into());
+        self
+    }
+
+    /// Sets the text that the key types in shift state `ss`.
+    pub fn text(mut self, ss: u8, text: impl Into<String>) -> Self {
+        self.outputs.insert(ss, KeyOutput::Text(text.into()));
+        self
+    }
+
+    /// Makes the key a dead key in shift state `ss`.
+    pub fn dead(mut self, ss: u8, c: char) -> Self {
+        self.outputs.insert(ss, KeyOutput::Dead(c));
+        self
+    }
+}
+
+/// Everything needed to build a `KeyboardLayout` without a live windows session: which virtual key each scan code maps to, and what each key produces in each shift state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutDescription {
+    pub id: HKL,
+    pub keys: Vec<KeyDescription>,
+    /// What the dead keys combine into, keyed by (dead key's character, base character).
+    pub compositions: BTreeMap<(char, char), char>,
+}
+
+impl LayoutDescription {
+    pub fn new(id: HKL) -> Self {
+        Self{
+            id,
+            keys: vec![],
+            compositions: BTreeMap::new(),
+        }
+    }
+
+    pub fn key(mut self, key: KeyDescription) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Makes the dead key that produces `dead` followed by a key typing `base` type `composed`.
+    pub fn compose(mut self, dead: char, base: char, composed: char) -> Self {
+        self.compositions.insert((dead, base), composed);
+        self
+    }
+
+    fn find(&self, vk: u8, sc: u8) -> Option<&KeyDescription> {
+        self.keys.iter().find(|k| k.sc == sc && k.vk == vk)
+    }
+
+    fn output(&self, vk: u8, sc: u8, ss: u8) -> Option<&KeyOutput> {
+        self.find(vk, sc).and_then(|k| k.outputs.get(&ss))
+    }
+}
+
+impl LayoutProbe for &LayoutDescription {
+    fn sc_to_vk(&self, sc: u8) -> Option<u8> {
+        self.keys.iter().find(|k| k.sc == sc).map(|k| k.vk)
+    }
+
+    fn to_unichr(&mut self, vk: u8, sc: u8, ss: u8) -> (Option<Vec<u16>>, bool) {
+        match self.output(vk, sc, ss) {
+            Some(KeyOutput::Text(text)) => (Some(text.encode_utf16().collect()), false),
+            Some(KeyOutput::Dead(c)) => (Some(c.encode_utf16(&mut [0; 2]).to_vec()), true),
+            None => (None, false),
+        }
+    }
+
+    fn to_unichr_caps(&mut self, vk: u8, sc: u8, ss: u8) -> Option<Vec<u16>> {
+        let key = self.find(vk, sc)?;
+        if let Some(text) = key.caps_outputs.get(&ss) {
+            return Some(text.encode_utf16().collect());
+        }
+        let ss = if key.caps_lock && ss & !SHIFT_STATE_SHIFT == 0 { ss ^ SHIFT_STATE_SHIFT } else { ss };
+        match key.outputs.get(&ss) {
+            Some(KeyOutput::Text(text)) => Some(text.encode_utf16().collect()),
+            _ => None,
+        }
+    }
+
+    fn compose(&mut self, (dead_vk, dead_sc, dead_ss): (u8, u8, u8), (vk, sc, ss): (u8, u8, u8)) -> Option<Vec<u16>> {
+        let Some(KeyOutput::Dead(dead)) = self.output(dead_vk, dead_sc, dead_ss) else { return None };
+        let Some(KeyOutput::Text(text)) = self.output(vk, sc, ss) else { return None };
+        let mut chars = text.chars();
+        let (Some(base), None) = (chars.next(), chars.next()) else { return None };
+        let composed = self.compositions.get(&(*dead, base))?;
+        Some(composed.encode_utf16(&mut [0; 2]).to_vec())
+    }
+}
+
+impl KeyboardLayout {
+    /// Builds a layout from a description instead of asking windows, so it works for layouts that aren't installed (or on other OSes).
+    pub fn from_description(desc: &LayoutDescription) -> Self {
+        Self::from_probe(desc.id, &mut &*desc, false)
+    }
+}
@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use std::collections::HashMap;
+
 use bimap::BiMap;
 
 use lazy_static::lazy_static;
@@ -410,6 +412,194 @@ lazy_static! {
         ("ydiaeresis"        ,'\u{ff}'), // ÿ
         ("yen"               ,'\u{a5}'), // ¥
     ].into_iter());
+
+    /// AutoHotkey key names that aren't the same as the name in `VIRTUAL_KEY_TO_CODE`, upper case.
+    pub(crate) static ref AHK_KEY_NAME_TO_VIRTUAL_KEY: HashMap<&'static str, &'static str> = HashMap::from_iter([
+        ("ENTER",            "RETURN"),
+        ("ESC",              "ESCAPE"),
+        ("BS",               "BACK"),
+        ("BACKSPACE",        "BACK"),
+        ("DEL",              "DELETE"),
+        ("INS",              "INSERT"),
+        ("PGUP",             "PRIOR"),
+        ("PGDN",             "NEXT"),
+        ("CAPSLOCK",         "CAPITAL"),
+        ("SCROLLLOCK",       "SCROLL"),
+        ("CTRL",             "CONTROL"),
+        ("LCTRL",            "LCONTROL"),
+        ("RCTRL",            "RCONTROL"),
+        ("ALT",              "MENU"),
+        ("LALT",             "LMENU"),
+        ("RALT",             "RMENU"),
+        ("APPSKEY",          "APPS"),
+        ("PRINTSCREEN",      "SNAPSHOT"),
+        ("CTRLBREAK",        "CANCEL"),
+        ("NUMPADDOT",        "DECIMAL"),
+        ("NUMPADMULT",       "MULTIPLY"),
+        ("NUMPADDIV",        "DIVIDE"),
+        ("NUMPADADD",        "ADD"),
+        ("NUMPADSUB",        "SUBTRACT"),
+        // Sent as the main Enter key, which nearly every program treats the same
+        ("NUMPADENTER",      "RETURN"),
+        ("MEDIA_NEXT",       "MEDIA_NEXT_TRACK"),
+        ("MEDIA_PREV",       "MEDIA_PREV_TRACK"),
+        ("LAUNCH_MEDIA",     "LAUNCH_MEDIA_SELECT"),
+    ]);
+
+    /// Linux evdev keycodes (`KEY_*` from linux/input-event-codes.h) for the physical key that sends each virtual key.
+    /// The generic SHIFT, CONTROL and MENU codes go to the left-hand keys.
+    pub(crate) static ref VIRTUAL_CODE_TO_EVDEV: HashMap<u8, u16> = HashMap::from_iter([
+        (VK_ESCAPE,               1),     // KEY_ESC
+        (VK_DIGIT1,               2),     // KEY_1
+        (VK_DIGIT2,               3),     // KEY_2
+        (VK_DIGIT3,               4),     // KEY_3
+        (VK_DIGIT4,               5),     // KEY_4
+        (VK_DIGIT5,               6),     // KEY_5
+        (VK_DIGIT6,               7),     // KEY_6
+        (VK_DIGIT7,               8),     // KEY_7
+        (VK_DIGIT8,               9),     // KEY_8
+        (VK_DIGIT9,               10),    // KEY_9
+        (VK_DIGIT0,               11),    // KEY_0
+        (VK_OEM_MINUS,            12),    // KEY_MINUS
+        (VK_OEM_PLUS,             13),    // KEY_EQUAL
+        (VK_BACK,                 14),    // KEY_BACKSPACE
+        (VK_TAB,                  15),    // KEY_TAB
+        (VK_Q,                    16),    // KEY_Q
+        (VK_W,                    17),    // KEY_W
+        (VK_E,                    18),    // KEY_E
+        (VK_R,                    19),    // KEY_R
+        (VK_T,                    20),    // KEY_T
+        (VK_Y,                    21),    // KEY_Y
+        (VK_U,                    22),    // KEY_U
+        (VK_I,                    23),    // KEY_I
+        (VK_O,                    24),    // KEY_O
+        (VK_P,                    25),    // KEY_P
+        (VK_OEM_4,                26),    // KEY_LEFTBRACE
+        (VK_OEM_6,                27),    // KEY_RIGHTBRACE
+        (VK_RETURN,               28),    // KEY_ENTER
+        (VK_CONTROL,              29),    // KEY_LEFTCTRL
+        (VK_LCONTROL,             29),    // KEY_LEFTCTRL
+        (VK_A,                    30),    // KEY_A
+        (VK_S,                    31),    // KEY_S
+        (VK_D,                    32),    // KEY_D
+        (VK_F,                    33),    // KEY_F
+        (VK_G,                    34),    // KEY_G
+        (VK_H,                    35),    // KEY_H
+        (VK_J,                    36),    // KEY_J
+        (VK_K,                    37),    // KEY_K
+        (VK_L,                    38),    // KEY_L
+        (VK_OEM_1,                39),    // KEY_SEMICOLON
+        (VK_OEM_7,                40),    // KEY_APOSTROPHE
+        (VK_OEM_3,                41),    // KEY_GRAVE
+        (VK_SHIFT,                42),    // KEY_LEFTSHIFT
+        (VK_LSHIFT,               42),    // KEY_LEFTSHIFT
+        (VK_OEM_5,                43),    // KEY_BACKSLASH
+        (VK_Z,                    44),    // KEY_Z
+        (VK_X,                    45),    // KEY_X
+        (VK_C,                    46),    // KEY_C
+        (VK_V,                    47),    // KEY_V
+        (VK_B,                    48),    // KEY_B
+        (VK_N,                    49),    // KEY_N
+        (VK_M,                    50),    // KEY_M
+        (VK_OEM_COMMA,            51),    // KEY_COMMA
+        (VK_OEM_PERIOD,           52),    // KEY_DOT
+        (VK_OEM_2,                53),    // KEY_SLASH
+        (VK_RSHIFT,               54),    // KEY_RIGHTSHIFT
+        (VK_MULTIPLY,             55),    // KEY_KPASTERISK
+        (VK_MENU,                 56),    // KEY_LEFTALT
+        (VK_LMENU,                56),    // KEY_LEFTALT
+        (VK_SPACE,                57),    // KEY_SPACE
+        (VK_CAPITAL,              58),    // KEY_CAPSLOCK
+        (VK_F1,                   59),    // KEY_F1
+        (VK_F2,                   60),    // KEY_F2
+        (VK_F3,                   61),    // KEY_F3
+        (VK_F4,                   62),    // KEY_F4
+        (VK_F5,                   63),    // KEY_F5
+        (VK_F6,                   64),    // KEY_F6
+        (VK_F7,                   65),    // KEY_F7
+        (VK_F8,                   66),    // KEY_F8
+        (VK_F9,                   67),    // KEY_F9
+        (VK_F10,                  68),    // KEY_F10
+        (VK_NUMLOCK,              69),    // KEY_NUMLOCK
+        (VK_SCROLL,               70),    // KEY_SCROLLLOCK
+        (VK_NUMPAD7,              71),    // KEY_KP7
+        (VK_NUMPAD8,              72),    // KEY_KP8
+        (VK_NUMPAD9,              73),    // KEY_KP9
+        (VK_SUBTRACT,             74),    // KEY_KPMINUS
+        (VK_NUMPAD4,              75),    // KEY_KP4
+        (VK_NUMPAD5,              76),    // KEY_KP5
+        (VK_NUMPAD6,              77),    // KEY_KP6
+        (VK_ADD,                  78),    // KEY_KPPLUS
+        (VK_NUMPAD1,              79),    // KEY_KP1
+        (VK_NUMPAD2,              80),    // KEY_KP2
+        (VK_NUMPAD3,              81),    // KEY_KP3
+        (VK_NUMPAD0,              82),    // KEY_KP0
+        (VK_DECIMAL,              83),    // KEY_KPDOT
+        (VK_OEM_102,              86),    // KEY_102ND
+        (VK_F11,                  87),    // KEY_F11
+        (VK_F12,                  88),    // KEY_F12
+        (VK_CONVERT,              92),    // KEY_HENKAN
+        (VK_NONCONVERT,           94),    // KEY_MUHENKAN
+        (VK_RCONTROL,             97),    // KEY_RIGHTCTRL
+        (VK_DIVIDE,               98),    // KEY_KPSLASH
+        (VK_SNAPSHOT,             99),    // KEY_SYSRQ
+        (VK_RMENU,                100),   // KEY_RIGHTALT
+        (VK_HOME,                 102),   // KEY_HOME
+        (VK_UP,                   103),   // KEY_UP
+        (VK_PRIOR,                104),   // KEY_PAGEUP
+        (VK_LEFT,                 105),   // KEY_LEFT
+        (VK_RIGHT,                106),   // KEY_RIGHT
+        (VK_END,                  107),   // KEY_END
+        (VK_DOWN,                 108),   // KEY_DOWN
+        (VK_NEXT,                 109),   // KEY_PAGEDOWN
+        (VK_INSERT,               110),   // KEY_INSERT
+        (VK_DELETE,               111),   // KEY_DELETE
+        (VK_VOLUME_MUTE,          113),   // KEY_MUTE
+        (VK_VOLUME_DOWN,          114),   // KEY_VOLUMEDOWN
+        (VK_VOLUME_UP,            115),   // KEY_VOLUMEUP
+        (VK_PAUSE,                119),   // KEY_PAUSE
+        (VK_SEPARATOR,            121),   // KEY_KPCOMMA
+        (VK_KANA,                 122),   // KEY_HANGEUL
+        (VK_HANJA,                123),   // KEY_HANJA
+        (VK_LWIN,                 125),   // KEY_LEFTMETA
+        (VK_RWIN,                 126),   // KEY_RIGHTMETA
+        (VK_APPS,                 127),   // KEY_COMPOSE
+        (VK_BROWSER_STOP,         128),   // KEY_STOP
+        (VK_HELP,                 138),   // KEY_HELP
+        (VK_LAUNCH_APP2,          140),   // KEY_CALC
+        (VK_SLEEP,                142),   // KEY_SLEEP
+        (VK_LAUNCH_MAIL,          155),   // KEY_MAIL
+        (VK_BROWSER_FAVORITES,    156),   // KEY_BOOKMARKS
+        (VK_LAUNCH_APP1,          157),   // KEY_COMPUTER
+        (VK_BROWSER_BACK,         158),   // KEY_BACK
+        (VK_BROWSER_FORWARD,      159),   // KEY_FORWARD
+        (VK_MEDIA_NEXT_TRACK,     163),   // KEY_NEXTSONG
+        (VK_MEDIA_PLAY_PAUSE,     164),   // KEY_PLAYPAUSE
+        (VK_MEDIA_PREV_TRACK,     165),   // KEY_PREVIOUSSONG
+        (VK_MEDIA_STOP,           166),   // KEY_STOPCD
+        (VK_BROWSER_HOME,         172),   // KEY_HOMEPAGE
+        (VK_BROWSER_REFRESH,      173),   // KEY_REFRESH
+        (VK_F13,                  183),   // KEY_F13
+        (VK_F14,                  184),   // KEY_F14
+        (VK_F15,                  185),   // KEY_F15
+        (VK_F16,                  186),   // KEY_F16
+        (VK_F17,                  187),   // KEY_F17
+        (VK_F18,                  188),   // KEY_F18
+        (VK_F19,                  189),   // KEY_F19
+        (VK_F20,                  190),   // KEY_F20
+        (VK_F21,                  191),   // KEY_F21
+        (VK_F22,                  192),   // KEY_F22
+        (VK_F23,                  193),   // KEY_F23
+        (VK_F24,                  194),   // KEY_F24
+        (VK_PLAY,                 207),   // KEY_PLAY
+        (VK_PRINT,                210),   // KEY_PRINT
+        (VK_BROWSER_SEARCH,       217),   // KEY_SEARCH
+        (VK_CANCEL,               223),   // KEY_CANCEL
+        (VK_LAUNCH_MEDIA_SELECT,  226),   // KEY_MEDIA
+        (VK_SELECT,               0x161), // KEY_SELECT
+        (VK_CLEAR,                0x163), // KEY_CLEAR
+        (VK_ZOOM,                 0x174), // KEY_ZOOM
+    ]);
 }
 
 
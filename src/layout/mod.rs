@@ -1,21 +1,28 @@
 use std::borrow::Cow;
-use std::ffi::OsString;
-use std::os::windows::ffi::OsStringExt;
 use std::collections::HashMap;
 
-use bimap::BiMap;
-
-use windows::Win32::UI::WindowsAndMessaging as wm_sys;
-use windows::Win32::UI::Input::KeyboardAndMouse as km_sys;
-
-pub use windows::Win32::UI::TextServices::HKL;
-
 pub mod maps;
 use maps::*;
 
-const SHIFT_STATE_SHIFT:u8 = 0x01;
-const SHIFT_STATE_CTRL:u8  = 0x02;
-const SHIFT_STATE_MENU:u8  = 0x04;
+#[cfg(windows)]
+mod win32;
+#[cfg(windows)]
+pub use win32::*;
+
+mod description;
+pub use description::*;
+mod bundled;
+pub use bundled::*;
+mod code_page;
+pub use code_page::*;
+#[cfg(feature = "serde")]
+mod snapshot;
+
+pub const SHIFT_STATE_SHIFT:u8 = 0x01;
+pub const SHIFT_STATE_CTRL:u8  = 0x02;
+pub const SHIFT_STATE_MENU:u8  = 0x04;
+/// Ctrl+Alt, which is what AltGr looks like to ToUnicodeEx on layouts that have it
+pub const SHIFT_STATE_ALTGR:u8 = SHIFT_STATE_CTRL | SHIFT_STATE_MENU;
 
 const SHIFT_STATES:[u8; 8] = [0, 1, 2, 3, 4, 5, 6, 7,];
 
@@ -38,6 +45,7 @@ fn shift_state_str(ss:u8) -> String {
     }
 }
 
+#[allow(dead_code)]
 fn get_vk_code(mut name: &str) -> Option<u8> {
     if name == "HANGUEL" || name == "HANGUL" { name = "KANA" }
     if name == "KANJI" { name = "HANJA" }
@@ -52,29 +60,52 @@ fn vk_to_str(vk:u8) -> Cow<'static, str> {
     }
 }
 
-/// Calls GetKeyboardLayout(GetWindowThreadProcessId(GetForegroundWindow()), NULL)
-/// Important note from windows docs:
-///
-/// > Since the keyboard layout can be dynamically changed, applications that cache information about the current keyboard layout should process the WM_INPUTLANGCHANGE message to be informed of changes in the input language.
-pub fn current_layout_id() -> HKL {
-    // "The foreground window can be NULL in certain circumstances, such as when a window is losing activation." augh
-    let front_window = unsafe { wm_sys::GetForegroundWindow() };
-    if front_window.0 == 0 { panic!("Result of GetForegroundWindow was 0 (NULL)"); }
-    let pid = unsafe { wm_sys::GetWindowThreadProcessId(
-        front_window,
-        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getwindowthreadprocessid
-        // "A pointer to a variable that receives the process identifier. If this parameter is not NULL, ..." ie this can be null
-        std::ptr::null_mut(),
-    ) };
-    unsafe { km_sys::GetKeyboardLayout(pid) }
+/// A keyboard layout handle, as returned by GetKeyboardLayout. The low word is the language identifier and the high word is the device handle for the physical layout.
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct HKL(pub isize);
+
+/// Source of the raw per-key information that a `KeyboardLayout` is built from. On windows this is answered by MapVirtualKeyExW and ToUnicodeEx, but it can be anything that knows what each key does.
+pub trait LayoutProbe {
+    /// The virtual key that the given scan code maps to, if any.
+    fn sc_to_vk(&self, sc: u8) -> Option<u8>;
+
+    /// The UTF-16 text that pressing `vk` in the shift state `ss` produces, and whether it is a dead key. For dead keys the text is what the dead key produces when followed by a space.
+    fn to_unichr(&mut self, vk: u8, sc: u8, ss: u8) -> (Option<Vec<u16>>, bool);
+
+    /// The UTF-16 text that pressing `vk` in the shift state `ss` produces while Caps Lock is on. None if that can't be told, in which case Caps Lock is assumed not to matter.
+    fn to_unichr_caps(&mut self, vk: u8, sc: u8, ss: u8) -> Option<Vec<u16>> {
+        let _ = (vk, sc, ss);
+        None
+    }
+
+    /// The UTF-16 text produced by pressing the dead key `dead` followed by `base`, both given as (virtual key, scan code, shift state). When the two don't combine this is None, or the dead key's character followed by the base key's.
+    fn compose(&mut self, dead: (u8, u8, u8), base: (u8, u8, u8)) -> Option<Vec<u16>> {
+        let _ = (dead, base);
+        None
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug,Clone,PartialEq,Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyboardLayout {
     layout_id: HKL,
+    #[cfg_attr(feature = "serde", serde(with = "snapshot::string_keys"))]
     char_to_vk_ss: HashMap<char, (u8, u8)>,
+    #[cfg_attr(feature = "serde", serde(with = "snapshot::sorted"))]
     keyname_to_vk: HashMap<Cow<'static, str>,u8>,
+    #[cfg_attr(feature = "serde", serde(with = "snapshot::string_keys"))]
     ss_to_vks: HashMap<u8, std::vec::Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(default, with = "snapshot::pair_keys"))]
+    vk_ss_to_char: HashMap<(u8, u8), char>,
+    #[cfg_attr(feature = "serde", serde(default, with = "snapshot::pair_keys"))]
+    vk_ss_to_dead_char: HashMap<(u8, u8), char>,
+    #[cfg_attr(feature = "serde", serde(default, with = "snapshot::pair_keys"))]
+    dead_key_compositions: HashMap<(char, char), char>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    has_altgr: bool,
+    #[cfg_attr(feature = "serde", serde(default, with = "snapshot::pair_keys"))]
+    vk_ss_to_char_caps: HashMap<(u8, u8), char>,
 }
 
 impl KeyboardLayout {
@@ -94,74 +125,70 @@ impl KeyboardLayout {
         &self.ss_to_vks
     }
 
-    pub fn current() -> Self {
-        Self::new(current_layout_id(), false)
+    /// Every character that some key types, keyed by (virtual key, shift state). Unlike `char_to_vk_ss` this includes every key that types a character, not just the easiest way to type it.
+    pub fn vk_ss_to_char(&self) -> &HashMap<(u8, u8), char> {
+        &self.vk_ss_to_char
     }
 
-    pub fn new(layout_id: HKL, debug: bool) -> Self {
-        let mut sc_to_vk = BiMap::new();
-        for sc in 1..=0x7fu8 {
-            let vk = unsafe { km_sys::MapVirtualKeyExW(sc.into(), wm_sys::MAPVK_VSC_TO_VK_EX, layout_id) };
-            if vk != 0 {
-                sc_to_vk.insert(sc, vk.try_into().unwrap());
-            }
-        }
+    /// The dead keys, keyed by (virtual key, shift state). The value is what the dead key types when followed by a space.
+    pub fn vk_ss_to_dead_char(&self) -> &HashMap<(u8, u8), char> {
+        &self.vk_ss_to_dead_char
+    }
 
-        // This is for lpKeyState param of ToUnicodeEx, "A pointer to a 256-byte array that contains the current keyboard state. Each element (byte) in the array contains the state of one key. If the high-order bit of a byte is set, the key is down."
-        let mut state = [0u8; 256];
-        let mut strbuf = [0u16; 8];
+    /// Whether the right Alt key is AltGr (KLLF_ALTGR). This is worked out from whether any key types a character with Ctrl+Alt, since that's the only way ToUnicodeEx shows it.
+    pub fn has_altgr(&self) -> bool {
+        self.has_altgr
+    }
 
-        let mut fill_state = |ss:u8, state: &mut [u8; 256]| {
-            for (mod_state, mod_vk) in [
-                (SHIFT_STATE_SHIFT, VK_SHIFT),
-                (SHIFT_STATE_CTRL,  VK_CONTROL),
-                (SHIFT_STATE_MENU,  VK_MENU),
-            ] {
-                state[mod_vk as usize] = if ss & mod_state != 0 { 0x80 } else { 0 }
-            }
-        };
-
-        let mut to_unichr = |vk:u8, sc:u8, ss:u8| {
-            fill_state(ss, &mut state);
-
-            let rc = unsafe {
-                km_sys::ToUnicodeEx(
-                    vk.into(),
-                    sc.into(),
-                    &state,
-                    strbuf.as_mut_slice(),
-                    0,
-                    layout_id,
-                )
-            };
-
-            let dead_key;
-            let mut c = None;
-            if rc > 0 {
-                dead_key = false;
-                c = Some(strbuf[0..(rc as usize)].to_vec());
-            } else if rc < 0 {
-                // This was a "dead key"; it produces nothing by itself, but will aglutinate onto the next character. "Pressing" space should produce the character we want by itself.
-                dead_key = true;
-                fill_state(0, &mut state);
-                let rc2 = unsafe {
-                    km_sys::ToUnicodeEx(
-                        VK_SPACE.into(),
-                        (*sc_to_vk.get_by_right(&VK_SPACE).unwrap()).into(),
-                        &state,
-                        strbuf.as_mut_slice(),
-                        0,
-                        layout_id,
-                    )
-                };
-                if rc2 > 0 {
-                    c = Some(strbuf[0..(rc2 as usize)].to_vec());
-                }
-            } else { // rc == 0
-                dead_key = false;
+    /// What each dead key combines into, keyed by (dead key's character, base character). Only combinations that type a single character are included.
+    pub fn dead_key_compositions(&self) -> &HashMap<(char, char), char> {
+        &self.dead_key_compositions
+    }
+
+    /// What keys type instead while Caps Lock is on, keyed by (virtual key, shift state). Only keys that Caps Lock changes are included.
+    pub fn vk_ss_to_char_caps(&self) -> &HashMap<(u8, u8), char> {
+        &self.vk_ss_to_char_caps
+    }
+
+    /// Whether characters can be typed in shift state `ss` without it being a shortcut.
+    fn is_plannable(&self, ss: u8) -> bool {
+        ss & SHIFT_STATE_CTRL == 0 || (self.has_altgr && ss & SHIFT_STATE_ALTGR == SHIFT_STATE_ALTGR)
+    }
+
+    /// The easiest (virtual key, shift state) that types `c`, taking Caps Lock into account. With `caps_lock` false this is the same as `char_to_vk_ss`.
+    pub fn vk_ss_for_char(&self, c: char, caps_lock: bool) -> Option<(u8, u8)> {
+        let best = self.char_to_vk_ss.get(&c).copied();
+        if !caps_lock || best.is_some_and(|vk_ss| !self.vk_ss_to_char_caps.contains_key(&vk_ss)) {
+            return best;
+        }
+        let typed_with_caps = |vk_ss| self.vk_ss_to_char_caps.get(vk_ss).or_else(|| self.vk_ss_to_char.get(vk_ss));
+        let mut candidates:Vec<(u8, u8)> = self.vk_ss_to_char.keys()
+            .filter(|(_, ss)| self.is_plannable(*ss))
+            .filter(|vk_ss| typed_with_caps(vk_ss) == Some(&c))
+            .copied()
+            .collect();
+        candidates.sort_unstable_by_key(|(vk, ss)| (ss.count_ones(), *vk, *ss));
+        candidates.first().copied()
+    }
+
+    /// The easiest way to type `c` as a dead key followed by a base key, as ((dead vk, dead ss), (base vk, base ss)), if the layout can.
+    pub fn dead_key_sequence(&self, c: char, caps_lock: bool) -> Option<((u8, u8), (u8, u8))> {
+        let mut sequences = vec![];
+        for ((dead_c, base_c), _) in self.dead_key_compositions.iter().filter(|(_, composed)| **composed == c) {
+            let Some(base) = self.vk_ss_for_char(*base_c, caps_lock) else { continue };
+            for (dead, _) in self.vk_ss_to_dead_char.iter().filter(|(_, dc)| *dc == dead_c) {
+                sequences.push((*dead, base));
             }
-            (c, dead_key)
-        };
+        }
+        sequences.into_iter().min_by_key(|((dead_vk, dead_ss), (base_vk, base_ss))| {
+            (dead_ss.count_ones() + base_ss.count_ones(), *dead_vk, *dead_ss, *base_vk, *base_ss)
+        })
+    }
+
+    /// Builds a layout by asking `probe` what every key does in every shift state. If `debug` is true, every key that produces something is printed to stderr.
+    pub fn from_probe(layout_id: HKL, probe: &mut impl LayoutProbe, debug: bool) -> Self {
+        let mut sc_vk:Vec<(u8, u8)> = (1..=0x7fu8).filter_map(|sc| Some((sc, probe.sc_to_vk(sc)?))).collect();
+        sc_vk.sort_unstable();
 
         fn sort_vk_ss_list(mut l:Vec<(u8,u8)>) -> Vec<(u8,u8)> {
             l.sort_unstable_by_key(|(_, ss)| ss.count_ones());
@@ -170,9 +197,12 @@ impl KeyboardLayout {
 
         let mut char_to_vk_sss:HashMap<char, Vec<(u8, u8)>> = HashMap::new();
         let mut keyname_to_vk_sss:HashMap<Cow<'static, str>, Vec<(u8, u8)>> = HashMap::new();
+        let mut vk_ss_to_char = HashMap::new();
+        let mut vk_ss_to_dead_char = HashMap::new();
+        // (sc, vk, ss, char) of everything found, to try the dead keys against afterwards
+        let mut dead_key_list = vec![];
+        let mut char_key_list = vec![];
 
-        let mut sc_vk:Vec<(u8, u8)> = sc_to_vk.iter().map(|(a, b)| (*a, *b)).collect();
-        sc_vk.sort_unstable();
         for (sc, vk) in sc_vk {
             for ss_ref in &SHIFT_STATES {
                 let ss = *ss_ref;
@@ -180,34 +210,34 @@ impl KeyboardLayout {
                     // "Alt and Shift+Alt don't work, so skip them."
                     continue;
                 }
-                let (maybe_c, dead_key) = to_unichr(vk, sc, ss);
+                let (maybe_c, dead_key) = probe.to_unichr(vk, sc, ss);
                 if debug {
                     if let Some(c) = maybe_c.as_ref() {
                         eprintln!(
                             "{}{} -> {:?} [{:?}] {}",
                             shift_state_str(ss),
                             vk_to_str(vk),
-                            OsString::from_wide(c.as_ref()),
+                            String::from_utf16_lossy(c),
                             c,
                             if dead_key { "[dead key]" } else { "" }
                         );
                     }
                 }
-                let mut kn:Option<Cow<'static, str>> = None;
                 if let Some(c_vec) = maybe_c.as_ref() {
-                    if c_vec.len() == 1 {
-                        let c_u16:u16 = c_vec[0];
-                        let c_u8:u8   = c_u16.try_into().unwrap();
-                        let c_u32:u32 = c_u16.into();
-                        let c_char:char = c_u32.try_into().unwrap();
-                        kn = KEYNAME_TO_CHAR.get_by_right(&c_char).copied().map(std::convert::Into::into);
+                    // A lone surrogate can't be a char, so it's treated like any other key that types more than one code unit
+                    if let (1, Some(c_char)) = (c_vec.len(), char::from_u32(c_vec[0].into())) {
+                        let mut kn:Option<Cow<'static, str>> = KEYNAME_TO_CHAR.get_by_right(&c_char).copied().map(std::convert::Into::into);
                         if dead_key {
+                            vk_ss_to_dead_char.insert((vk, ss), c_char);
+                            dead_key_list.push((sc, vk, ss, c_char));
                             if let Some(old_kn) = kn {
                                 let maybe_key:Option<Cow<'static, str>> = DEAD_KEYNAME.get_by_left(old_kn.as_ref()).copied().map(std::convert::Into::into);
                                 kn = Some(maybe_key.unwrap_or(format!("dead_{}", old_kn.as_ref()).into()));
                             }
                         } else {
                             char_to_vk_sss.entry(c_char).or_default().push((vk, ss));
+                            vk_ss_to_char.insert((vk, ss), c_char);
+                            char_key_list.push((sc, vk, ss, c_char));
                         }
                         if let Some(kn) = kn {
                             keyname_to_vk_sss.entry(kn).or_default().push((vk, ss));
@@ -217,11 +247,43 @@ impl KeyboardLayout {
             }
         }
 
+        let mut dead_key_compositions = HashMap::new();
+        for (dead_sc, dead_vk, dead_ss, dead_c) in &dead_key_list {
+            for (sc, vk, ss, base_c) in &char_key_list {
+                let composed = probe.compose((*dead_vk, *dead_sc, *dead_ss), (*vk, *sc, *ss));
+                // When they don't combine, windows gives back both characters
+                if let Some(&[unit]) = composed.as_deref() {
+                    if let Some(c) = char::from_u32(unit.into()) {
+                        if debug {
+                            eprintln!("{} + {} -> {:?}", dead_c, base_c, c);
+                        }
+                        dead_key_compositions.entry((*dead_c, *base_c)).or_insert(c);
+                    }
+                }
+            }
+        }
+
+        let mut vk_ss_to_char_caps = HashMap::new();
+        for (sc, vk, ss, c) in &char_key_list {
+            if let Some(&[unit]) = probe.to_unichr_caps(*vk, *sc, *ss).as_deref() {
+                match char::from_u32(unit.into()) {
+                    Some(caps_c) if caps_c != *c => { vk_ss_to_char_caps.insert((*vk, *ss), caps_c); },
+                    _ => (),
+                }
+            }
+        }
+
+        let has_altgr = char_key_list.iter().any(|(_, _, ss, _)| ss & SHIFT_STATE_ALTGR == SHIFT_STATE_ALTGR);
+        // Anything else held with Ctrl is a shortcut rather than a character, eg Ctrl+C types "\x03" but also copies
+        let plannable = |ss:u8| ss & SHIFT_STATE_CTRL == 0 || (has_altgr && ss & SHIFT_STATE_ALTGR == SHIFT_STATE_ALTGR);
+
         let mut char_to_vk_ss = HashMap::new();
-        for (c, vk_ss_list) in char_to_vk_sss {
-            char_to_vk_ss.insert(c, sort_vk_ss_list(vk_ss_list)[0]);
+        for (c, mut vk_ss_list) in char_to_vk_sss {
+            vk_ss_list.retain(|(_, ss)| plannable(*ss));
+            if !vk_ss_list.is_empty() {
+                char_to_vk_ss.insert(c, sort_vk_ss_list(vk_ss_list)[0]);
+            }
         }
-        char_to_vk_ss.insert('\n',char_to_vk_ss[&'\r'].clone());
 
         let mut keyname_to_vk:HashMap<Cow<'static, str>,u8> = HashMap::new();
         for (vk, kn) in VIRTUAL_CODE_TO_ALT_NAME.iter() {
@@ -235,11 +297,20 @@ impl KeyboardLayout {
         for (kn, vk_ss_list) in keyname_to_vk_sss {
             keyname_to_vk.insert(kn, sort_vk_ss_list(vk_ss_list)[0].0);
         }
-        // add_modifiers_aliases(&mut keyname_to_vk);
+        add_modifiers_aliases(&mut keyname_to_vk);
 
         let mut ss_to_vks = HashMap::new();
         for ss in SHIFT_STATES {
             let mut vk_list = vec![];
+            if has_altgr && ss & SHIFT_STATE_ALTGR == SHIFT_STATE_ALTGR {
+                // What a real keyboard sends for AltGr: a fake left Control, then right Alt
+                if ss & SHIFT_STATE_SHIFT > 0 {
+                    vk_list.push(VK_SHIFT);
+                }
+                vk_list.extend([VK_LCONTROL, VK_RMENU]);
+                ss_to_vks.insert(ss, vk_list);
+                continue;
+            }
             for (mod_state, mod_vk) in [
                 (SHIFT_STATE_SHIFT, VK_SHIFT),
                 (SHIFT_STATE_CTRL,  VK_CONTROL),
@@ -257,6 +328,42 @@ impl KeyboardLayout {
             char_to_vk_ss,
             keyname_to_vk,
             ss_to_vks,
+            vk_ss_to_char,
+            vk_ss_to_dead_char,
+            dead_key_compositions,
+            has_altgr,
+            vk_ss_to_char_caps,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Names for modifiers that don't say which side, and other common spellings, so that combos like "ctrl+shift+t" work. The left key is used for either side.
+fn add_modifiers_aliases(keyname_to_vk: &mut HashMap<Cow<'static, str>,u8>) {
+    for (name, vk) in [
+        ("shift",     VK_LSHIFT),
+        ("ctrl",      VK_LCONTROL),
+        ("control",   VK_LCONTROL),
+        ("ctrl_l",    VK_LCONTROL),
+        ("ctrl_r",    VK_RCONTROL),
+        ("alt",       VK_LMENU),
+        ("win",       VK_LWIN),
+        ("win_l",     VK_LWIN),
+        ("win_r",     VK_RWIN),
+        ("super",     VK_LWIN),
+    ] {
+        keyname_to_vk.entry(name.into()).or_insert(vk);
+    }
+}
+
+#[cfg(not(windows))]
+impl KeyboardLayout {
+    /// Outside of windows there's no way to ask what layout is in use, so this is always the US layout, which is what the evdev keycodes sent by the uinput backend are named after.
+    pub fn current() -> Self {
+        Self::us()
+    }
+
+    /// Outside of windows this is always the bundled layout `klid`, or US if that isn't bundled.
+    pub fn current_or_bundled(klid: &str) -> Self {
+        Self::bundled(klid).unwrap_or_else(Self::us)
+    }
+}
@@ -0,0 +1,99 @@
+//! Saving and loading `KeyboardLayout`s, so a layout captured on one machine can be used anywhere.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::KeyboardLayout;
+
+/// Writes maps in key order, so that snapshots of the same layout are byte-for-byte identical and diff nicely.
+pub(super) mod sorted {
+    use super::*;
+
+    pub fn serialize<K: Ord + Serialize, V: Serialize, S: Serializer>(map: &HashMap<K, V>, s: S) -> Result<S::Ok, S::Error> {
+        map.iter().collect::<BTreeMap<_, _>>().serialize(s)
+    }
+
+    pub fn deserialize<'de, K: Eq + Hash + Deserialize<'de>, V: Deserialize<'de>, D: Deserializer<'de>>(d: D) -> Result<HashMap<K, V>, D::Error> {
+        HashMap::deserialize(d)
+    }
+}
+
+/// Like `sorted`, but writes keys as strings since TOML doesn't allow anything else.
+pub(super) mod string_keys {
+    use super::*;
+    use serde::de::Error;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<K: Ord + Display, V: Serialize, S: Serializer>(map: &HashMap<K, V>, s: S) -> Result<S::Ok, S::Error> {
+        let sorted:BTreeMap<&K, &V> = map.iter().collect();
+        s.collect_map(sorted.into_iter().map(|(k, v)| (k.to_string(), v)))
+    }
+
+    pub fn deserialize<'de, K: Eq + Hash + FromStr, V: Deserialize<'de>, D: Deserializer<'de>>(d: D) -> Result<HashMap<K, V>, D::Error> {
+        HashMap::<String, V>::deserialize(d)?
+            .into_iter()
+            .map(|(k, v)| Ok((k.parse().map_err(|_| D::Error::custom(format!("invalid key {:?}", k)))?, v)))
+            .collect()
+    }
+}
+
+/// Writes maps keyed by pairs, like (virtual key, shift state), as a sorted list of `[a, b, value]`, since neither JSON nor TOML can have tuples as keys.
+pub(super) mod pair_keys {
+    use super::*;
+
+    pub fn serialize<A, B, V, S>(map: &HashMap<(A, B), V>, s: S) -> Result<S::Ok, S::Error>
+    where A: Ord + Serialize, B: Ord + Serialize, V: Ord + Serialize, S: Serializer {
+        let mut entries:Vec<(&A, &B, &V)> = map.iter().map(|((a, b), v)| (a, b, v)).collect();
+        entries.sort_unstable();
+        entries.serialize(s)
+    }
+
+    pub fn deserialize<'de, A, B, V, D>(d: D) -> Result<HashMap<(A, B), V>, D::Error>
+    where A: Eq + Hash + Deserialize<'de>, B: Eq + Hash + Deserialize<'de>, V: Deserialize<'de>, D: Deserializer<'de> {
+        Ok(Vec::<(A, B, V)>::deserialize(d)?.into_iter().map(|(a, b, v)| ((a, b), v)).collect())
+    }
+}
+
+fn invalid_data(e: impl std::fmt::Display) -> crate::Error {
+    crate::Error::InvalidData(e.to_string())
+}
+
+impl KeyboardLayout {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> crate::Result<Self> {
+        serde_json::from_str(s).map_err(invalid_data)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap()
+    }
+
+    pub fn from_toml(s: &str) -> crate::Result<Self> {
+        toml::from_str(s).map_err(invalid_data)
+    }
+
+    /// Writes the layout to `path`, as TOML if the extension is `.toml` and JSON otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+        let contents = if is_toml(path) { self.to_toml() } else { self.to_json() };
+        Ok(std::fs::write(path, contents)?)
+    }
+
+    /// Reads a layout written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        if is_toml(path) { Self::from_toml(&contents) } else { Self::from_json(&contents) }
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
@@ -0,0 +1,176 @@
+use bimap::BiMap;
+
+use windows::Win32::UI::WindowsAndMessaging as wm_sys;
+use windows::Win32::UI::Input::KeyboardAndMouse as km_sys;
+use windows::Win32::UI::TextServices as ts_sys;
+
+use super::*;
+
+impl From<HKL> for ts_sys::HKL {
+    fn from(hkl: HKL) -> Self {
+        Self(hkl.0)
+    }
+}
+
+impl From<ts_sys::HKL> for HKL {
+    fn from(hkl: ts_sys::HKL) -> Self {
+        Self(hkl.0)
+    }
+}
+
+/// Calls GetKeyboardLayout(GetWindowThreadProcessId(GetForegroundWindow()), NULL)
+/// Important note from windows docs:
+///
+/// > Since the keyboard layout can be dynamically changed, applications that cache information about the current keyboard layout should process the WM_INPUTLANGCHANGE message to be informed of changes in the input language.
+///
+/// # Panics
+///
+/// Panics if there is no foreground window. See `try_current_layout_id`.
+pub fn current_layout_id() -> HKL {
+    try_current_layout_id().expect("Result of GetForegroundWindow was 0 (NULL)")
+}
+
+/// Like `current_layout_id`, but fails with `Error::NoForegroundWindow` instead of panicking.
+pub fn try_current_layout_id() -> crate::Result<HKL> {
+    // "The foreground window can be NULL in certain circumstances, such as when a window is losing activation." augh
+    let front_window = unsafe { wm_sys::GetForegroundWindow() };
+    if front_window.0 == 0 { return Err(crate::Error::NoForegroundWindow); }
+    let pid = unsafe { wm_sys::GetWindowThreadProcessId(
+        front_window,
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getwindowthreadprocessid
+        // "A pointer to a variable that receives the process identifier. If this parameter is not NULL, ..." ie this can be null
+        std::ptr::null_mut(),
+    ) };
+    Ok(unsafe { km_sys::GetKeyboardLayout(pid) }.into())
+}
+
+/// Asks windows what each key does using MapVirtualKeyExW and ToUnicodeEx
+struct Win32Probe {
+    layout_id: ts_sys::HKL,
+    sc_to_vk: BiMap<u8, u8>,
+    // This is for lpKeyState param of ToUnicodeEx, "A pointer to a 256-byte array that contains the current keyboard state. Each element (byte) in the array contains the state of one key. If the high-order bit of a byte is set, the key is down."
+    state: [u8; 256],
+    strbuf: [u16; 8],
+}
+
+impl Win32Probe {
+    fn new(layout_id: HKL) -> Self {
+        let layout_id = layout_id.into();
+        let mut sc_to_vk = BiMap::new();
+        for sc in 1..=0x7fu8 {
+            let vk = unsafe { km_sys::MapVirtualKeyExW(sc.into(), wm_sys::MAPVK_VSC_TO_VK_EX, layout_id) };
+            if let Ok(vk @ 1..) = u8::try_from(vk) {
+                sc_to_vk.insert(sc, vk);
+            }
+        }
+        Self{
+            layout_id,
+            sc_to_vk,
+            state: [0u8; 256],
+            strbuf: [0u16; 8],
+        }
+    }
+
+    fn fill_state(&mut self, ss:u8) {
+        for (mod_state, mod_vk) in [
+            (SHIFT_STATE_SHIFT, VK_SHIFT),
+            (SHIFT_STATE_CTRL,  VK_CONTROL),
+            (SHIFT_STATE_MENU,  VK_MENU),
+        ] {
+            self.state[mod_vk as usize] = if ss & mod_state != 0 { 0x80 } else { 0 }
+        }
+    }
+
+    /// Calls ToUnicodeEx, leaving the result in `strbuf`. Note that windows remembers dead keys between calls.
+    fn translate(&mut self, vk:u8, sc:u8, ss:u8) -> i32 {
+        self.fill_state(ss);
+        unsafe {
+            km_sys::ToUnicodeEx(
+                vk.into(),
+                sc.into(),
+                &self.state,
+                self.strbuf.as_mut_slice(),
+                0,
+                self.layout_id,
+            )
+        }
+    }
+
+    fn press_space(&mut self) -> i32 {
+        // Every layout has space on 0x39, but just in case
+        let sc = self.sc_to_vk.get_by_right(&VK_SPACE).copied().unwrap_or(0x39);
+        self.translate(VK_SPACE, sc, 0)
+    }
+}
+
+impl LayoutProbe for Win32Probe {
+    fn sc_to_vk(&self, sc: u8) -> Option<u8> {
+        self.sc_to_vk.get_by_left(&sc).copied()
+    }
+
+    fn to_unichr(&mut self, vk:u8, sc:u8, ss:u8) -> (Option<Vec<u16>>, bool) {
+        let rc = self.translate(vk, sc, ss);
+
+        let dead_key;
+        let mut c = None;
+        if rc > 0 {
+            dead_key = false;
+            c = Some(self.strbuf[0..(rc as usize)].to_vec());
+        } else if rc < 0 {
+            // This was a "dead key"; it produces nothing by itself, but will aglutinate onto the next character. "Pressing" space should produce the character we want by itself.
+            dead_key = true;
+            let rc2 = self.press_space();
+            if rc2 > 0 {
+                c = Some(self.strbuf[0..(rc2 as usize)].to_vec());
+            }
+        } else { // rc == 0
+            dead_key = false;
+        }
+        (c, dead_key)
+    }
+
+    fn to_unichr_caps(&mut self, vk:u8, sc:u8, ss:u8) -> Option<Vec<u16>> {
+        self.state[VK_CAPITAL as usize] = 0x01;
+        let rc = self.translate(vk, sc, ss);
+        self.state[VK_CAPITAL as usize] = 0;
+        if rc < 0 {
+            self.press_space();
+            return None;
+        }
+        (rc > 0).then(|| self.strbuf[0..(rc as usize)].to_vec())
+    }
+
+    fn compose(&mut self, dead: (u8, u8, u8), base: (u8, u8, u8)) -> Option<Vec<u16>> {
+        let (vk, sc, ss) = dead;
+        if self.translate(vk, sc, ss) >= 0 {
+            return None;
+        }
+        let (vk, sc, ss) = base;
+        let rc = self.translate(vk, sc, ss);
+        if rc < 0 {
+            // Some dead keys chain onto each other; space gets rid of whatever is still pending
+            self.press_space();
+            return None;
+        }
+        (rc > 0).then(|| self.strbuf[0..(rc as usize)].to_vec())
+    }
+}
+
+impl KeyboardLayout {
+    /// The layout of the foreground window, or the bundled US layout if there is no foreground window.
+    pub fn current() -> Self {
+        Self::current_or_bundled(US_KLID)
+    }
+
+    /// The layout of the foreground window. If there is no foreground window, the bundled layout `klid` is used instead, or US if that isn't bundled.
+    pub fn current_or_bundled(klid: &str) -> Self {
+        match try_current_layout_id() {
+            Ok(layout_id) => Self::new(layout_id, false),
+            Err(_) => Self::bundled(klid).unwrap_or_else(Self::us),
+        }
+    }
+
+    pub fn new(layout_id: HKL, debug: bool) -> Self {
+        Self::from_probe(layout_id, &mut Win32Probe::new(layout_id), debug)
+    }
+}
@@ -1,6 +1,22 @@
+pub mod error;
 pub mod input;
 pub mod window_message;
 pub mod layout;
 pub mod send;
+pub mod sink;
+pub mod simulate;
+pub mod key_state;
+pub mod clipboard;
+pub mod pace;
+pub mod combo;
+pub mod ahk;
+pub mod script;
+pub mod monitor;
+#[cfg(target_os = "linux")]
+pub mod uinput;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub mod x11;
+
+pub use layout::KeyboardLayout;
+pub use error::{Error, Result};
 
-pub use layout::KeyboardLayout;
\ No newline at end of file
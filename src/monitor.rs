@@ -0,0 +1,177 @@
+//! Where the monitors are, for moving the mouse to a pixel.
+//!
+//! Absolute mouse movements are in normalized coordinates, where 0..=65535 covers the primary monitor or the whole virtual desktop. Windows turns a normalized coordinate `n` into the pixel `n * size / 65536`, rounded down, so `Rect::pixel_to_normalized` rounds up to land on exactly the pixel asked for.
+//!
+//! `Monitors::current` asks the OS for the layout, and tests can build one by hand with `Monitors::new`.
+
+use crate::{Error, Result};
+use crate::input::MouseMovement;
+
+/// A rectangle of the virtual desktop, in pixels. 0,0 is the top-left of the primary monitor, so monitors left of or above it have negative coordinates.
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq,Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self{x, y, width, height}
+    }
+
+    /// The first column to the right of the rectangle
+    pub fn right(&self) -> i64 {
+        i64::from(self.x) + i64::from(self.width)
+    }
+
+    /// The first row below the rectangle
+    pub fn bottom(&self) -> i64 {
+        i64::from(self.y) + i64::from(self.height)
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (i64::from(self.x)..self.right()).contains(&x.into()) && (i64::from(self.y)..self.bottom()).contains(&y.into())
+    }
+
+    /// The smallest rectangle that covers both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect{
+            x,
+            y,
+            width: (self.right().max(other.right()) - i64::from(x)) as u32,
+            height: (self.bottom().max(other.bottom()) - i64::from(y)) as u32,
+        }
+    }
+
+    /// The normalized coordinates that Windows turns back into the pixel `x`, `y`, or None if it isn't inside the rectangle.
+    pub fn pixel_to_normalized(&self, x: i32, y: i32) -> Option<(u16, u16)> {
+        fn scale(offset: i64, len: u32) -> u16 {
+            // Rounding up, (offset * 65536 / len) * len / 65536 comes back to offset for any len up to 65536
+            ((offset * 65536 + i64::from(len) - 1) / i64::from(len)).min(65535) as u16
+        }
+        if !self.contains(x, y) {
+            return None;
+        }
+        Some((scale(i64::from(x) - i64::from(self.x), self.width), scale(i64::from(y) - i64::from(self.y), self.height)))
+    }
+
+    /// Maps a 0..=65535 normalized coordinate pair onto a pixel inside this rectangle, the same way Windows does.
+    pub fn normalized_to_pixel(&self, x: u16, y: u16) -> (i32, i32) {
+        fn scale(n: u16, len: u32) -> i32 {
+            (u64::from(n) * u64::from(len) / 65536) as i32
+        }
+        (self.x + scale(x, self.width), self.y + scale(y, self.height))
+    }
+}
+
+/// One monitor
+#[derive(Debug,Clone,PartialEq)]
+pub struct Monitor {
+    pub rect: Rect,
+    pub primary: bool,
+    /// How much bigger than at 96 DPI things are drawn, eg 1.5 at 144 DPI. Coordinates are always in physical pixels, so callers that work in scaled units multiply by this first.
+    pub scale: f64,
+}
+
+impl Monitor {
+    pub fn new(rect: Rect, primary: bool) -> Self {
+        Self{rect, primary, scale: 1.0}
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// The monitors that make up the virtual desktop.
+#[derive(Debug,Default,Clone,PartialEq)]
+pub struct Monitors {
+    /// The primary monitor first, then the rest from left to right and top to bottom
+    pub monitors: Vec<Monitor>,
+}
+
+impl Monitors {
+    /// Sorts the monitors the same way as `current` does, so their indices don't depend on the order they're listed in.
+    pub fn new(mut monitors: Vec<Monitor>) -> Self {
+        monitors.sort_by_key(|m| (!m.primary, m.rect.x, m.rect.y));
+        Self{monitors}
+    }
+
+    pub fn primary(&self) -> Option<&Monitor> {
+        self.monitors.iter().find(|m| m.primary)
+    }
+
+    /// The rectangle around every monitor, which is what `MouseMovement::AbsoluteVirtualDesktop` covers
+    pub fn virtual_desktop(&self) -> Rect {
+        let mut rects = self.monitors.iter().map(|m| m.rect);
+        let first = rects.next().unwrap_or_default();
+        rects.fold(first, |all, rect| all.union(&rect))
+    }
+
+    /// The monitor showing the pixel `x`, `y`, if any
+    pub fn monitor_at(&self, x: i32, y: i32) -> Option<&Monitor> {
+        self.monitors.iter().find(|m| m.rect.contains(x, y))
+    }
+
+    /// The movement that puts the mouse on the pixel `x`, `y` of the virtual desktop. Fails with `Error::OffScreen` if no monitor shows that pixel, since Windows would move the mouse to the nearest one instead.
+    pub fn move_to_pixel(&self, x: i32, y: i32) -> Result<MouseMovement> {
+        self.monitor_at(x, y).ok_or(Error::OffScreen{x, y})?;
+        let (x, y) = self.virtual_desktop().pixel_to_normalized(x, y).ok_or(Error::OffScreen{x, y})?;
+        Ok(MouseMovement::AbsoluteVirtualDesktop{x, y})
+    }
+
+    /// The movement that puts the mouse on the pixel `x`, `y` counting from the top-left of `self.monitors[monitor]`. Like with `move_to_pixel`, the pixel only has to be on some monitor, not necessarily that one.
+    pub fn move_to_monitor_relative(&self, monitor: usize, x: i32, y: i32) -> Result<MouseMovement> {
+        let rect = self.monitors.get(monitor).ok_or(Error::NoSuchMonitor(monitor))?.rect;
+        self.move_to_pixel(rect.x.saturating_add(x), rect.y.saturating_add(y))
+    }
+
+    /// The monitors according to EnumDisplayMonitors. The scale is the effective DPI over 96, or 1 before Windows 8.1.
+    ///
+    /// Unless the process is per-monitor DPI aware, Windows scales what it reports on high DPI monitors, and the pixels won't be physical ones.
+    #[cfg(windows)]
+    pub fn current() -> Result<Self> {
+        use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+        use windows::Win32::Graphics::Gdi as gdi_sys;
+        use windows::Win32::UI::HiDpi as dpi_sys;
+        use windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY;
+
+        unsafe extern "system" fn add(handle: gdi_sys::HMONITOR, _dc: gdi_sys::HDC, _clip: *mut RECT, data: LPARAM) -> BOOL {
+            let monitors = &mut *(data.0 as *mut Vec<Monitor>);
+            let mut info = gdi_sys::MONITORINFO{cbSize: std::mem::size_of::<gdi_sys::MONITORINFO>() as u32, ..Default::default()};
+            if !gdi_sys::GetMonitorInfoW(handle, &mut info).as_bool() {
+                return false.into();
+            }
+            let r = info.rcMonitor;
+            let (mut dpi_x, mut dpi_y) = (0, 0);
+            let scale = match dpi_sys::GetDpiForMonitor(handle, dpi_sys::MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) {
+                Ok(()) => f64::from(dpi_x) / 96.0,
+                Err(_) => 1.0,
+            };
+            monitors.push(Monitor{
+                rect: Rect::new(r.left, r.top, (r.right - r.left) as u32, (r.bottom - r.top) as u32),
+                primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+                scale,
+            });
+            true.into()
+        }
+
+        let mut monitors:Vec<Monitor> = vec![];
+        let ok = unsafe { gdi_sys::EnumDisplayMonitors(gdi_sys::HDC(0), std::ptr::null(), Some(add), LPARAM(&mut monitors as *mut _ as isize)) };
+        if !ok.as_bool() {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self::new(monitors))
+    }
+
+    /// There's no portable way to ask outside of windows, so this always fails.
+    #[cfg(not(windows))]
+    pub fn current() -> Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "There is no way to list the monitors on this platform").into())
+    }
+}
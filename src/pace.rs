@@ -0,0 +1,194 @@
+//! Sending inputs a few at a time, for programs that drop keys when sent too many at once.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::input::{HeldKeys, Input};
+use crate::{Error, Result};
+use crate::sink::{InputSink, SendReport, send_all};
+
+/// Where `send_paced` gets the time from and waits on, so that tests can fake it.
+pub trait Clock {
+    /// Time since some fixed point, such as when the clock was made.
+    fn now(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+impl<C: Clock + ?Sized> Clock for &mut C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// The real time, with `thread::sleep`.
+#[derive(Debug,Copy,Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self{start: Instant::now()}
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// A clock that only moves when slept on, and remembers every sleep.
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct FakeClock {
+    pub now: Duration,
+    pub sleeps: Vec<Duration>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Moves the time on without it counting as a sleep, eg to pretend sending took a while.
+    pub fn advance(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.now += duration;
+        self.sleeps.push(duration);
+    }
+}
+
+/// How fast `send_paced` goes.
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub struct Pacing {
+    /// Keystrokes sent in each batch, see `strokes`.
+    pub strokes_per_chunk: usize,
+    pub strokes_per_second: f64,
+}
+
+impl Pacing {
+    /// Typing speed in characters, which are taken to be one keystroke each. Fails if the speed is NaN or infinite.
+    pub fn chars_per_second(chars_per_second: f64, strokes_per_chunk: usize) -> Result<Self> {
+        if !chars_per_second.is_finite() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} isn't a typing speed", chars_per_second)).into());
+        }
+        Ok(Self{strokes_per_chunk, strokes_per_second: chars_per_second})
+    }
+
+    /// Typing speed in words per minute, with the usual 5 characters to a word.
+    pub fn words_per_minute(wpm: f64, strokes_per_chunk: usize) -> Result<Self> {
+        Self::chars_per_second(wpm * 5.0 / 60.0, strokes_per_chunk)
+    }
+
+    /// The time from the start of one batch to the start of the next. No time at all if `strokes_per_second` isn't positive, and `Duration::MAX` if it's so slow that the time doesn't fit in a `Duration`.
+    pub fn chunk_interval(&self) -> Duration {
+        if self.strokes_per_second.is_nan() || self.strokes_per_second <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::try_from_secs_f64(self.strokes_per_chunk.max(1) as f64 / self.strokes_per_second).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Where each keystroke of `inputs` ends, see `strokes`.
+fn stroke_ends(inputs: &[Input]) -> Vec<usize> {
+    let mut ends = vec![];
+    let mut held = HeldKeys::new();
+    for (i, input) in inputs.iter().enumerate() {
+        held.track(input);
+        if held.is_empty() {
+            ends.push(i + 1);
+        }
+    }
+    if ends.last().copied().unwrap_or(0) < inputs.len() {
+        ends.push(inputs.len());
+    }
+    ends
+}
+
+/// Splits `inputs` into keystrokes, each running from the first key going down until every key is up again. So a key's down and up, a shortcut and its modifiers, and both halves of a surrogate pair always stay in the same keystroke. Anything left with keys still down at the end makes up the last keystroke.
+pub fn strokes(inputs: &[Input]) -> Vec<&[Input]> {
+    chunks(inputs, 1)
+}
+
+/// Groups the keystrokes of `inputs` into batches of `strokes_per_chunk`.
+pub fn chunks(inputs: &[Input], strokes_per_chunk: usize) -> Vec<&[Input]> {
+    let mut start = 0;
+    stroke_ends(inputs).chunks(strokes_per_chunk.max(1)).map(|ends| {
+        let end = *ends.last().unwrap();
+        let chunk = &inputs[start..end];
+        start = end;
+        chunk
+    }).collect()
+}
+
+/// Sends `inputs` in batches, starting each batch one `pacing.chunk_interval()` after the one before. Time spent sending counts towards the wait. If a batch took longer than that, the next one goes straight away, but there's no catching up with bigger bursts afterwards.
+///
+/// Each batch is sent with `sink::send_all`. If one isn't all accepted, nothing after it is sent, and the report in `Error::PartialSend` covers all of `inputs`.
+pub fn send_paced<S: InputSink, C: Clock>(
+    inputs: &[Input],
+    pacing: &Pacing,
+    retries: u32,
+    sink: &mut S,
+    clock: &mut C,
+) -> Result<SendReport> {
+    let interval = pacing.chunk_interval();
+    let mut due = clock.now();
+    let mut report = SendReport{
+        accepted: 0,
+        total: inputs.len(),
+        retries: 0,
+        released: vec![],
+        stuck: vec![],
+    };
+    for (i, chunk) in chunks(inputs, pacing.strokes_per_chunk).into_iter().enumerate() {
+        if i > 0 {
+            due = due.saturating_add(interval);
+            let now = clock.now();
+            if due > now {
+                clock.sleep(due - now);
+            } else {
+                due = now;
+            }
+        }
+        match send_all(chunk, retries, sink) {
+            Ok(sent) => {
+                report.accepted += sent.accepted;
+                report.retries += sent.retries;
+            },
+            Err(Error::PartialSend(sent)) => {
+                report.accepted += sent.accepted;
+                report.retries += sent.retries;
+                report.released = sent.released;
+                report.stuck = sent.stuck;
+                return Err(Error::PartialSend(report));
+            },
+            // Once something has been typed, a failing batch is still only a partial send
+            Err(e) if report.accepted == 0 => return Err(e),
+            Err(_) => return Err(Error::PartialSend(report)),
+        }
+    }
+    Ok(report)
+}
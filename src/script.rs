@@ -0,0 +1,405 @@
+//! A small macro language, compiled to a timeline of input batches and sleeps.
+//!
+//! ```text
+//! # Switch to the window before last
+//! down alt_l
+//! repeat 2 {
+//!     key tab
+//!     sleep 200ms
+//! }
+//! up alt_l
+//! type "Hello, \"world\"\n"
+//! key ctrl+s
+//! move 100 -20
+//! click left
+//! scroll -3
+//! ```
+//!
+//! There's one command per line, and `#` starts a comment. Keys are named as in `combo::Combo::parse`. `move` is relative, in pixels (subject to pointer acceleration), and `scroll` is in wheel clicks, positive going away from the user. Sleeps are in `ms` or `s`, and default to milliseconds. Repeats are unrolled when compiling, up to `MAX_UNROLLED_STATEMENTS` commands in all.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::{Error, Result};
+use crate::combo::{key_vk, Combo};
+use crate::input::{HeldKeys, Input, MouseButton, MouseInput, MouseInputEnum, MouseMovement, WHEEL_DELTA};
+use crate::key_state::KeyStateSource;
+use crate::layout::KeyboardLayout;
+use crate::pace::Clock;
+use crate::send::{inputs_for_text_with_options, key_event, TextOptions};
+use crate::sink::{send_all, InputSink, SendReport};
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Statement {
+    Type(String),
+    Key(Combo),
+    Down(u8),
+    Up(u8),
+    Sleep(Duration),
+    Repeat(u32, Vec<Statement>),
+    Move{dx: i32, dy: i32},
+    Click(MouseButton),
+    /// In wheel clicks
+    Scroll(i32),
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    Open,
+    Close,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct Token {
+    /// Byte offset into the whole script
+    at: usize,
+    kind: TokenKind,
+}
+
+/// Splits one line, which starts at byte `line_at` of `source`, into tokens
+fn tokenize(source: &str, line_at: usize, line: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at = line_at + i;
+        match c {
+            '#' => break,
+            c if c.is_whitespace() => (),
+            '{' => tokens.push(Token{at, kind: TokenKind::Open}),
+            '}' => tokens.push(Token{at, kind: TokenKind::Close}),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(Error::syntax_at(source, at, "unterminated string")),
+                        Some((_, '"')) => break,
+                        Some((j, '\\')) => match chars.next() {
+                            Some((_, 'n')) => s.push('\n'),
+                            Some((_, 't')) => s.push('\t'),
+                            Some((_, 'r')) => s.push('\r'),
+                            Some((_, c @ ('"' | '\\'))) => s.push(c),
+                            _ => return Err(Error::syntax_at(source, line_at + j, "unknown escape, expected \\n, \\t, \\r, \\\" or \\\\")),
+                        },
+                        Some((_, c)) => s.push(c),
+                    }
+                }
+                tokens.push(Token{at, kind: TokenKind::Str(s)});
+            },
+            c => {
+                let mut word = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !"{}\"#".contains(*c)) {
+                    word.push(c);
+                }
+                tokens.push(Token{at, kind: TokenKind::Word(word)});
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses durations like "200ms", "1.5s" or "200", which is in milliseconds
+pub fn parse_duration(word: &str) -> Option<Duration> {
+    let (number, scale) = if let Some(ms) = word.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = word.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        (word, 0.001)
+    };
+    let number:f64 = number.parse().ok()?;
+    Duration::try_from_secs_f64(number * scale).ok()
+}
+
+fn parse_button(word: &str) -> Option<MouseButton> {
+    match word.to_lowercase().as_str() {
+        "left" => Some(MouseButton::Left),
+        "right" => Some(MouseButton::Right),
+        "middle" => Some(MouseButton::Middle),
+        "x1" => Some(MouseButton::X1),
+        "x2" => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+/// How many commands a script can come to once its repeats are unrolled, counting each character typed and each time round a repeat as at least one, so that a typo in a repeat count can't take all the memory when compiling
+pub const MAX_UNROLLED_STATEMENTS:u64 = 1_000_000;
+
+/// A parsed script
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+impl Script {
+    /// Key names are looked up in `layout` as the script is parsed, so that unknown ones are reported with their position.
+    pub fn parse(source: &str, layout: &KeyboardLayout) -> Result<Self> {
+        // The blocks that are still open, as where their "repeat" is, its count and what's in it so far
+        let mut blocks:Vec<(usize, u32, Vec<Statement>)> = vec![];
+        let mut statements = vec![];
+        // How many commands the script and each open block come to with the repeats unrolled, see MAX_UNROLLED_STATEMENTS
+        let mut sizes = vec![0u64];
+        let mut line_at = 0;
+        for line in source.split_inclusive('\n') {
+            let tokens = tokenize(source, line_at, line)?;
+            line_at += line.len();
+            let Some(first) = tokens.first() else { continue };
+            let args = &tokens[1..];
+            let error = |token: &Token, message: &str| Error::syntax_at(source, token.at, message);
+            // Where an argument that's missing would have been
+            let end = Token{at: line_at - line.len() + line.trim_end().len(), kind: TokenKind::Close};
+            let word = |i: usize, what: &str| match args.get(i) {
+                Some(Token{kind: TokenKind::Word(w), ..}) => Ok(w.as_str()),
+                Some(token) => Err(error(token, &format!("expected {}", what))),
+                None => Err(error(&end, &format!("expected {}", what))),
+            };
+            let no_more = |n: usize| match args.get(n) {
+                Some(token) => Err(error(token, "unexpected extra argument")),
+                None => Ok(()),
+            };
+            let number = |i: usize, what: &str| -> Result<i32> {
+                word(i, what)?.parse().map_err(|_| error(&args[i], &format!("expected {}", what)))
+            };
+
+            let command = match &first.kind {
+                TokenKind::Word(w) => w.to_lowercase(),
+                TokenKind::Close => {
+                    no_more(0)?;
+                    let (at, count, body) = blocks.pop().ok_or_else(|| error(first, "\"}\" without a repeat to close"))?;
+                    // Each time round counts as at least one, or empty repeats inside each other could loop for ever without adding anything
+                    let size = sizes.pop().unwrap_or(0).max(1).saturating_mul(count.into());
+                    let total = sizes.last_mut().unwrap();
+                    *total = total.saturating_add(size);
+                    if *total > MAX_UNROLLED_STATEMENTS {
+                        return Err(Error::syntax_at(source, at, "repeat makes the script too long to compile"));
+                    }
+                    let statement = Statement::Repeat(count, body);
+                    blocks.last_mut().map_or(&mut statements, |(_, _, body)| body).push(statement);
+                    continue;
+                },
+                _ => return Err(error(first, "expected a command")),
+            };
+            let statement = match command.as_str() {
+                "type" => {
+                    let text = match args.first() {
+                        Some(Token{kind: TokenKind::Str(s), ..}) => s.clone(),
+                        Some(token) => return Err(error(token, "expected a quoted string")),
+                        None => return Err(error(&end, "expected a quoted string")),
+                    };
+                    no_more(1)?;
+                    Statement::Type(text)
+                },
+                "key" => {
+                    let combo = word(0, "a key combo like ctrl+s")?;
+                    no_more(1)?;
+                    Statement::Key(Combo::parse(combo, layout).map_err(|e| error(&args[0], &e.to_string()))?)
+                },
+                "down" | "up" => {
+                    let name = word(0, "a key name")?;
+                    no_more(1)?;
+                    let vk = key_vk(name, layout).map_err(|e| error(&args[0], &e.to_string()))?;
+                    if command == "down" { Statement::Down(vk) } else { Statement::Up(vk) }
+                },
+                "sleep" => {
+                    let duration = parse_duration(word(0, "a duration like 200ms")?).ok_or_else(|| error(&args[0], "expected a duration like 200ms"))?;
+                    no_more(1)?;
+                    Statement::Sleep(duration)
+                },
+                "repeat" => {
+                    let count = word(0, "a repeat count")?.parse().map_err(|_| error(&args[0], "expected a repeat count"))?;
+                    match args.get(1) {
+                        Some(Token{kind: TokenKind::Open, ..}) => (),
+                        Some(token) => return Err(error(token, "expected \"{\"")),
+                        None => return Err(error(&end, "expected \"{\"")),
+                    }
+                    no_more(2)?;
+                    blocks.push((first.at, count, vec![]));
+                    sizes.push(0);
+                    continue;
+                },
+                "move" => {
+                    let (dx, dy) = (number(0, "a distance in pixels")?, number(1, "a distance in pixels")?);
+                    no_more(2)?;
+                    Statement::Move{dx, dy}
+                },
+                "click" => {
+                    let button = match args.first() {
+                        None => MouseButton::Left,
+                        Some(_) => parse_button(word(0, "a mouse button")?).ok_or_else(|| error(&args[0], "expected left, right, middle, x1 or x2"))?,
+                    };
+                    no_more(1)?;
+                    Statement::Click(button)
+                },
+                "scroll" => {
+                    let clicks = number(0, "a number of wheel clicks")?;
+                    no_more(1)?;
+                    Statement::Scroll(clicks)
+                },
+                _ => return Err(error(first, &format!("unknown command {:?}", command))),
+            };
+            *sizes.last_mut().unwrap() += match &statement {
+                Statement::Type(text) => text.chars().count().max(1) as u64,
+                _ => 1,
+            };
+            blocks.last_mut().map_or(&mut statements, |(_, _, body)| body).push(statement);
+        }
+        if let Some((at, _, _)) = blocks.last() {
+            return Err(Error::syntax_at(source, *at, "repeat without a closing \"}\""));
+        }
+        Ok(Self{statements})
+    }
+
+    /// Plans every statement for a keyboard in the given state, with `type` going through `inputs_for_text_with_options`.
+    pub fn compile<K: KeyStateSource>(&self, layout: &KeyboardLayout, state: &K, options: &TextOptions) -> Timeline {
+        let mut timeline = Timeline::default();
+        compile_into(&self.statements, layout, state, options, &mut timeline);
+        timeline
+    }
+}
+
+fn compile_into<K: KeyStateSource>(statements: &[Statement], layout: &KeyboardLayout, state: &K, options: &TextOptions, timeline: &mut Timeline) {
+    let msg = options.msg;
+    let mouse = |e| Input::from_mouse(&MouseInput{e, msg, time: None});
+    for statement in statements {
+        match statement {
+            Statement::Type(text) => {
+                let mut inputs = vec![];
+                inputs_for_text_with_options(text, layout, state, options, &mut inputs);
+                timeline.push_inputs(inputs);
+            },
+            Statement::Key(combo) => timeline.push_inputs(combo.inputs(msg)),
+            Statement::Down(vk) => timeline.push_inputs([Input::from_keyboard(&key_event(*vk, true, msg))]),
+            Statement::Up(vk) => timeline.push_inputs([Input::from_keyboard(&key_event(*vk, false, msg))]),
+            Statement::Sleep(duration) => timeline.push_sleep(*duration),
+            Statement::Repeat(count, body) => (0..*count).for_each(|_| compile_into(body, layout, state, options, timeline)),
+            Statement::Move{dx, dy} => timeline.push_inputs([mouse(MouseInputEnum::Move{m: MouseMovement::Relative{dx: *dx, dy: *dy}, coalesce: false})]),
+            Statement::Click(which) => timeline.push_inputs([false, true].map(|button_up| mouse(MouseInputEnum::Button{which: *which, button_up}))),
+            Statement::Scroll(clicks) => {
+                timeline.push_inputs([mouse(MouseInputEnum::Wheel{horizontal: false, amount: clicks.saturating_mul(WHEEL_DELTA.into())})]);
+            },
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Step {
+    /// Sent together, with `sink::send_all`
+    Inputs(Vec<Input>),
+    Sleep(Duration),
+}
+
+/// What a script does, as batches of input and the sleeps between them. Its `Display` lists every step, one per line, for dry runs.
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct Timeline {
+    pub steps: Vec<Step>,
+}
+
+impl Timeline {
+    /// Adds inputs to the last batch, or starts one if the last step was a sleep
+    pub fn push_inputs(&mut self, inputs: impl IntoIterator<Item = Input>) {
+        match self.steps.last_mut() {
+            Some(Step::Inputs(batch)) => batch.extend(inputs),
+            _ => {
+                let batch:Vec<Input> = inputs.into_iter().collect();
+                if !batch.is_empty() {
+                    self.steps.push(Step::Inputs(batch));
+                }
+            },
+        }
+    }
+
+    /// Adds a sleep, or lengthens the last one
+    pub fn push_sleep(&mut self, duration: Duration) {
+        match self.steps.last_mut() {
+            Some(Step::Sleep(d)) => *d += duration,
+            _ => self.steps.push(Step::Sleep(duration)),
+        }
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = &Input> {
+        self.steps.iter().flat_map(|step| match step {
+            Step::Inputs(batch) => batch.as_slice(),
+            Step::Sleep(_) => &[],
+        })
+    }
+
+    /// All the sleeps added up
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| match step {
+            Step::Sleep(d) => *d,
+            Step::Inputs(_) => Duration::ZERO,
+        }).sum()
+    }
+
+    /// Sends each batch and sleeps in between. If a batch is only partly accepted, the rest of the timeline isn't run, and the report covers every input up to then. Keys that are still down by then, including ones pressed in earlier batches, are let go, and those key-ups are in the report.
+    pub fn run<S: InputSink, C: Clock>(&self, retries: u32, sink: &mut S, clock: &mut C) -> Result<SendReport> {
+        let mut report = SendReport{
+            accepted: 0,
+            total: self.inputs().count(),
+            retries: 0,
+            released: vec![],
+            stuck: vec![],
+        };
+        let mut held = HeldKeys::new();
+        for step in &self.steps {
+            let batch = match step {
+                Step::Sleep(duration) => {
+                    clock.sleep(*duration);
+                    continue;
+                },
+                Step::Inputs(batch) => batch,
+            };
+            match send_all(batch, retries, sink) {
+                Ok(sent) => {
+                    report.accepted += sent.accepted;
+                    report.retries += sent.retries;
+                    held.track_all(batch);
+                },
+                Err(Error::PartialSend(sent)) => {
+                    report.accepted += sent.accepted;
+                    report.retries += sent.retries;
+                    // send_all has already tried to let go of what this batch pressed
+                    held.track_all(&batch[..sent.accepted]);
+                    held.track_all(sent.released.iter().chain(&sent.stuck));
+                    report.released = sent.released;
+                    report.stuck = sent.stuck;
+                    release(&held, sink, &mut report);
+                    return Err(Error::PartialSend(report));
+                },
+                Err(e) if report.accepted == 0 => return Err(e),
+                Err(_) => {
+                    release(&held, sink, &mut report);
+                    return Err(Error::PartialSend(report));
+                },
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Sends key-ups for the keys in `held`, noting in the report which went through
+fn release<S: InputSink>(held: &HeldKeys, sink: &mut S, report: &mut SendReport) {
+    let key_ups = held.key_ups();
+    if key_ups.is_empty() {
+        return;
+    }
+    let released = sink.send(&key_ups).map_or(0, |count| count as usize).min(key_ups.len());
+    report.released.extend_from_slice(&key_ups[..released]);
+    report.stuck.extend_from_slice(&key_ups[released..]);
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            match step {
+                Step::Sleep(duration) => writeln!(f, "sleep {:?}", duration)?,
+                Step::Inputs(batch) => {
+                    let inputs:Vec<String> = batch.iter().map(Input::to_string).collect();
+                    writeln!(f, "send {}", inputs.join(", "))?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
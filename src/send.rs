@@ -1,10 +1,20 @@
-///! The goal of this module is to convert from `str` to `Input`s that should produce that string when put through SendInput
+//! The goal of this module is to convert from `str` to `Input`s that should produce that string when put through SendInput
 
-use std::io;
 use std::borrow::Cow;
+use std::time::Duration;
 
-use crate::layout::KeyboardLayout;
-use crate::input::{Input, KeyboardInput, KeyboardInputEnum};
+use crate::{Error, Result};
+use crate::layout::{KeyboardLayout, CodePage, SHIFT_STATE_SHIFT};
+use crate::layout::maps::*;
+use crate::input::{Input, KeyboardInput, KeyboardInputEnum, MouseInput, MouseInputEnum, MouseMovement};
+use crate::key_state::{KeyState, KeyStateSource};
+use crate::sink::{InputSink, SendReport, default_sink, send_all};
+use crate::clipboard::{Clipboard, default_clipboard};
+use crate::pace::{Clock, Pacing, SystemClock, send_paced};
+use crate::script::Script;
+use crate::combo::{Combo, plover_combo_inputs};
+use crate::ahk::{inputs_for_ahk, parse as parse_ahk};
+use crate::monitor::Monitors;
 
 // This is taken directly from https://github.com/openstenoproject/plover/blob/2ada7c71cd25a114e1439817a44206ff0da8b70e/plover/oslayer/winkeyboardcontrol.py#L52 warts and all
 static EXTENDED_KEYS:phf::Set<u8> = phf::phf_set! {
@@ -64,22 +74,26 @@ impl Iterator for KeyPressIter {
             }
         }
 
-        let keycode = self.keycode_list[self.i];
-        let ki = key_event(keycode, !self.second_iter, self.msg.clone());
+        // Keys are let go in the opposite order they were pressed, so the modifiers stay held until the key is up
+        let keycode = if self.second_iter {
+            self.keycode_list[self.keycode_list.len() - 1 - self.i]
+        } else {
+            self.keycode_list[self.i]
+        };
+        let ki = key_event(keycode, !self.second_iter, self.msg);
 
         self.i += 1;
-        Some(Input::from_keyboard(&ki.into()))
+        Some(Input::from_keyboard(&ki))
     }
 }
 
-fn key_press(
-    c:char,
+fn key_press_vk_ss(
+    (vk, ss):(u8, u8),
     layout:&KeyboardLayout,
     msg: Option<super::window_message::WindowMessage>,
-) -> impl Iterator<Item = Input> {
-    let (vk, ss) = layout.char_to_vk_ss()[&c];
+) -> KeyPressIter {
     let mut keycode_list = vec![];
-    keycode_list.extend(&layout.ss_to_vks()[&ss]);
+    keycode_list.extend(layout.ss_to_vks().get(&ss).into_iter().flatten());
     keycode_list.push(vk);
 
     KeyPressIter {
@@ -90,7 +104,32 @@ fn key_press(
     }
 }
 
-fn key_unicode(
+pub(crate) fn key_tap(
+    keycode: u8,
+    msg: Option<super::window_message::WindowMessage>,
+) -> [Input; 2] {
+    [true, false].map(|key_down| Input::from_keyboard(&key_event(keycode, key_down, msg)))
+}
+
+/// How a single character gets typed
+enum CharPlan {
+    Key((u8, u8)),
+    DeadKey((u8, u8), (u8, u8)),
+    Unicode,
+}
+
+/// Each character is typed with the key that types it if there is one, then with a dead key followed by a base key, and finally as unicode.
+fn plan_char(c: char, layout: &KeyboardLayout, caps_lock: bool) -> CharPlan {
+    if let Some(vk_ss) = layout.vk_ss_for_char(c, caps_lock) {
+        CharPlan::Key(vk_ss)
+    } else if let Some((dead, base)) = layout.dead_key_sequence(c, caps_lock) {
+        CharPlan::DeadKey(dead, base)
+    } else {
+        CharPlan::Unicode
+    }
+}
+
+pub(crate) fn key_unicode(
     c:char,
     msg: Option<super::window_message::WindowMessage>,
 ) -> impl Iterator<Item = Input> {
@@ -101,77 +140,627 @@ fn key_unicode(
             let k = KeyboardInput{
                 e:KeyboardInputEnum::UnicodeCodeUnit(wc),
                 key_up,
-                msg: msg.clone(),
+                msg,
                 time: None,
             };
-            res.push(Input::from_keyboard(&k.into()));
+            res.push(Input::from_keyboard(&k));
         }
     }
     res.into_iter()
 }
 
-pub fn send_text_with_msg_layout(
+/// How characters that no key on the layout types are sent
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+pub enum CharFallback {
+    /// As unicode, with KEYEVENTF_UNICODE. Some older programs and remote desktop clients ignore these.
+    #[default]
+    Unicode,
+    /// Holding Alt and typing the character's code on the keypad, in the ANSI code page with a leading zero (Alt+0233 for é) or else in the OEM code page (Alt+130). Characters in neither are sent as unicode.
+    AltNumpad{oem: &'static CodePage, ansi: &'static CodePage},
+    /// Holding Alt and typing keypad plus and then the hex code point (Alt, +, e, 9 for é). Windows only does this with the `EnableHexNumpad` registry value set. Characters past U+FFFF are sent as unicode.
+    AltNumpadHex,
+}
+
+impl CharFallback {
+    /// `AltNumpad` with the system's code pages
+    pub fn alt_numpad() -> Self {
+        Self::AltNumpad{oem: CodePage::oem(), ansi: CodePage::ansi()}
+    }
+}
+
+/// The keys for an Alt+Numpad code: `digits` are hex or decimal digits, typed on the keypad, except for hex letters which use the letter keys. Num Lock is turned on for the code if it's off.
+pub(crate) fn key_alt_numpad(
+    digits: &str,
+    hex: bool,
+    num_lock: bool,
+    msg: Option<super::window_message::WindowMessage>,
+) -> impl Iterator<Item = Input> {
+    let mut res = vec![];
+    if !num_lock {
+        res.extend(key_tap(VK_NUMLOCK, msg));
+    }
+    res.push(Input::from_keyboard(&key_event(VK_LMENU, true, msg)));
+    if hex {
+        res.extend(key_tap(VK_ADD, msg));
+    }
+    for d in digits.bytes() {
+        let vk = match d {
+            b'0'..=b'9' => VK_NUMPAD0 + (d - b'0'),
+            _ => VK_A + (d - b'a'),
+        };
+        res.extend(key_tap(vk, msg));
+    }
+    res.push(Input::from_keyboard(&key_event(VK_LMENU, false, msg)));
+    if !num_lock {
+        res.extend(key_tap(VK_NUMLOCK, msg));
+    }
+    res.into_iter()
+}
+
+fn key_fallback(
+    c: char,
+    fallback: CharFallback,
+    num_lock: bool,
+    msg: Option<super::window_message::WindowMessage>,
+) -> Vec<Input> {
+    match fallback {
+        CharFallback::AltNumpad{oem, ansi} => {
+            if let Some(b) = ansi.encode(c) {
+                return key_alt_numpad(&format!("0{}", b), false, num_lock, msg).collect();
+            }
+            if let Some(b) = oem.encode(c) {
+                return key_alt_numpad(&b.to_string(), false, num_lock, msg).collect();
+            }
+        },
+        CharFallback::AltNumpadHex if u32::from(c) <= 0xFFFF => {
+            return key_alt_numpad(&format!("{:x}", u32::from(c)), true, num_lock, msg).collect();
+        },
+        _ => (),
+    }
+    key_unicode(c, msg).collect()
+}
+
+/// The shortcut that pastes in the target program
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+pub enum PasteShortcut {
+    #[default]
+    CtrlV,
+    ShiftInsert,
+    /// What most terminals use
+    CtrlShiftV,
+    /// These keys pressed in order and let go in reverse
+    Keys(&'static [u8]),
+}
+
+impl PasteShortcut {
+    pub fn vks(&self) -> &'static [u8] {
+        match self {
+            Self::CtrlV => &[VK_LCONTROL, VK_V],
+            Self::ShiftInsert => &[VK_LSHIFT, VK_INSERT],
+            Self::CtrlShiftV => &[VK_LCONTROL, VK_LSHIFT, VK_V],
+            Self::Keys(vks) => vks,
+        }
+    }
+}
+
+/// When and how to paste text instead of typing it
+///
+/// Only text on the clipboard is put back after pasting. Anything else that was there, such as an image or files, is lost, and the pasted text is left on the clipboard instead.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct PasteOptions {
+    pub shortcut: PasteShortcut,
+    /// Paste text at least this many characters long
+    pub min_len: usize,
+    /// Paste text where fewer than this percentage of the characters can be typed with keys on the layout
+    pub min_coverage_percent: u8,
+    /// How long to wait after pasting before putting the old clipboard back, since the target reads the clipboard in its own time
+    pub restore_delay: Duration,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        Self{
+            shortcut: PasteShortcut::default(),
+            min_len: 1000,
+            min_coverage_percent: 50,
+            restore_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl PasteOptions {
+    /// Whether `text` is long enough, or has few enough characters that `layout` can type, to be pasted.
+    pub fn should_paste(&self, text: &str, layout: &KeyboardLayout) -> bool {
+        let len = text.chars().count();
+        if len == 0 {
+            return false;
+        }
+        if len >= self.min_len {
+            return true;
+        }
+        let covered = text.chars().filter(|c| {
+            ['\r', '\n', '\t'].contains(c) || !matches!(plan_char(*c, layout, false), CharPlan::Unicode)
+        }).count();
+        covered * 100 < len * usize::from(self.min_coverage_percent)
+    }
+}
+
+/// What to press for a line break
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+pub enum NewlinePolicy {
+    #[default]
+    Enter,
+    /// Shift+Enter, which chat apps take as a line break instead of sending the message
+    ShiftEnter,
+}
+
+impl NewlinePolicy {
+    fn shift_state(&self) -> u8 {
+        match self {
+            Self::Enter => 0,
+            Self::ShiftEnter => SHIFT_STATE_SHIFT,
+        }
+    }
+}
+
+/// What to do with tab characters
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+pub enum TabPolicy {
+    /// Press Tab. In forms this moves focus to the next field.
+    #[default]
+    Key,
+    /// Type this many spaces instead
+    Spaces(u8),
+    /// Send the tab character as unicode, which most text boxes insert without moving focus
+    Unicode,
+}
+
+/// What to do with control characters other than line breaks and tabs
+#[derive(Debug,Default,Copy,Clone,PartialEq,Eq)]
+pub enum ControlCharPolicy {
+    /// Press the key that types it if there is one (eg Backspace for "\x08"), otherwise send it as unicode
+    #[default]
+    Send,
+    /// Leave them out
+    Drop,
+}
+
+/// How `send_text` and friends should go about typing.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct TextOptions {
+    /// Attached to every input, see `get_extra_info`
+    pub msg: Option<super::window_message::WindowMessage>,
+    /// Let go of any Shift, Ctrl, Alt or Win keys that are held before typing, and press them again afterwards. Otherwise a modifier that's still held from a hotkey turns the text into shortcuts.
+    pub release_held_modifiers: bool,
+    pub newline: NewlinePolicy,
+    /// Treat "\r\n" as a single line break rather than two. Defaults to true.
+    pub collapse_crlf: bool,
+    pub tab: TabPolicy,
+    pub control_chars: ControlCharPolicy,
+    pub fallback: CharFallback,
+    /// Paste text instead of typing it when these say so. Only the functions that take a clipboard, and `send_text_with_options`, paste.
+    pub paste: Option<PasteOptions>,
+    /// How many times to send the rest of the text again if only part of it was accepted, see `sink::send_all`
+    pub retries: u32,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self{
+            msg: None,
+            release_held_modifiers: false,
+            newline: NewlinePolicy::default(),
+            collapse_crlf: true,
+            tab: TabPolicy::default(),
+            control_chars: ControlCharPolicy::default(),
+            fallback: CharFallback::default(),
+            paste: None,
+            retries: 0,
+        }
+    }
+}
+
+/// Sends `text` assuming that no modifiers are held and Caps Lock is off. See `send_text_with_state`.
+pub fn send_text_with_msg_layout<S: InputSink>(
     text: &str,
     msg: Option<super::window_message::WindowMessage>,
-    layout: &KeyboardLayout
-) -> io::Result<()> {
+    layout: &KeyboardLayout,
+    sink: &mut S,
+) -> Result<SendReport> {
+    let options = TextOptions{msg, ..Default::default()};
+    send_text_with_state(text, layout, &KeyState::default(), &options, sink)
+}
+
+pub fn send_text_with_state<S: InputSink, K: KeyStateSource>(
+    text: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    sink: &mut S,
+) -> Result<SendReport> {
     let mut inputs = Vec::with_capacity(text.len());
-    for c in text.chars() {
-        if layout.char_to_vk_ss().contains_key(&c) {
-            inputs.extend(key_press(c, &layout, msg.clone()));
-        } else {
-            inputs.extend(key_unicode(c, msg.clone()));
-        }
+    inputs_for_text_with_options(text, layout, state, options, &mut inputs);
+    send_all(&inputs, options.retries, sink)
+}
+
+/// Like `send_text_with_state`, but pastes `text` through `clipboard` instead when `options.paste` says to.
+pub fn send_text_with_clipboard<S: InputSink, K: KeyStateSource, C: Clipboard>(
+    text: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    sink: &mut S,
+    clipboard: &mut C,
+) -> Result<SendReport> {
+    match options.paste {
+        Some(paste) if paste.should_paste(text, layout) => paste_text(text, &paste, options.msg, sink, clipboard),
+        _ => send_text_with_state(text, layout, state, options, sink),
+    }
+}
+
+/// Puts `text` on the clipboard, sends the paste shortcut and puts back whatever text was on the clipboard before, or empties it if it was empty. The old clipboard is put back even if sending fails. See `PasteOptions` for what happens to anything other than text.
+pub fn paste_text<S: InputSink, C: Clipboard>(
+    text: &str,
+    paste: &PasteOptions,
+    msg: Option<super::window_message::WindowMessage>,
+    sink: &mut S,
+    clipboard: &mut C,
+) -> Result<SendReport> {
+    paste_text_with_clock(text, paste, msg, sink, clipboard, &mut SystemClock::new())
+}
+
+/// Like `paste_text`, but waits `paste.restore_delay` on `clock`.
+pub fn paste_text_with_clock<S: InputSink, C: Clipboard, T: Clock>(
+    text: &str,
+    paste: &PasteOptions,
+    msg: Option<super::window_message::WindowMessage>,
+    sink: &mut S,
+    clipboard: &mut C,
+    clock: &mut T,
+) -> Result<SendReport> {
+    let saved = clipboard.get_text()?;
+    let was_empty = saved.is_none() && clipboard.is_empty()?;
+    clipboard.set_text(text)?;
+    let inputs:Vec<Input> = KeyPressIter{
+        keycode_list: paste.shortcut.vks().to_vec(),
+        i: 0,
+        second_iter: false,
+        msg,
+    }.collect();
+    let sent = send_all(&inputs, 0, sink);
+    if sent.is_ok() && !paste.restore_delay.is_zero() {
+        clock.sleep(paste.restore_delay);
     }
-    dbg!(inputs.len());
-    dbg!(crate::input::send_input(&inputs)).map(|_| ())
+    let restored = match saved {
+        Some(saved) => clipboard.set_text(&saved),
+        None if was_empty => clipboard.clear(),
+        None => Ok(()),
+    };
+    let report = sent?;
+    restored.map(|_| report)
+}
+
+/// Like `send_text_with_state`, but sends the text a few keystrokes at a time at the speed `pacing` says, for programs that drop keys when sent too many at once.
+pub fn send_text_paced<S: InputSink, K: KeyStateSource, C: Clock>(
+    text: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    pacing: &Pacing,
+    sink: &mut S,
+    clock: &mut C,
+) -> Result<SendReport> {
+    let mut inputs = Vec::with_capacity(text.len());
+    inputs_for_text_with_options(text, layout, state, options, &mut inputs);
+    send_paced(&inputs, pacing, options.retries, sink, clock)
 }
 
+/// Plans `text` assuming that Caps Lock is off. See `inputs_for_text_with_options`.
 pub fn inputs_for_text(
     text: &str,
     layout: &KeyboardLayout,
     out: &mut Vec<Input>
 ) {
-    for c in text.chars() {
-        if layout.char_to_vk_ss().contains_key(&c) {
-            out.extend(key_press(c, &layout, None));
-        } else {
-            out.extend(key_unicode(c, None));
+    inputs_for_text_with_options(text, layout, &KeyState::default(), &TextOptions::default(), out)
+}
+
+/// Plans `text` for a keyboard in the given state, with the default options. See `inputs_for_text_with_options`.
+pub fn inputs_for_text_with_state<K: KeyStateSource>(
+    text: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    out: &mut Vec<Input>
+) {
+    inputs_for_text_with_options(text, layout, state, &TextOptions::default(), out)
+}
+
+/// The modifiers that `TextOptions::release_held_modifiers` looks for
+const HELD_MODIFIERS:[u8; 8] = [VK_LSHIFT, VK_RSHIFT, VK_LCONTROL, VK_RCONTROL, VK_LMENU, VK_RMENU, VK_LWIN, VK_RWIN];
+
+/// Plans `text` for a keyboard in the given state.
+///
+/// Line breaks, tabs, other control characters and characters without a key are handled as `options` says. With Caps Lock on, letters are typed with the opposite shift state. If some character can only be typed with Caps Lock off (some SGCAPS keys), Caps Lock is instead turned off for the batch and back on after.
+pub fn inputs_for_text_with_options<K: KeyStateSource>(
+    text: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    out: &mut Vec<Input>
+) {
+    let msg = options.msg;
+    let held:Vec<u8> = if options.release_held_modifiers && !text.is_empty() {
+        HELD_MODIFIERS.into_iter().filter(|vk| state.is_down(*vk)).collect()
+    } else {
+        vec![]
+    };
+    // Alt or Win going up with nothing pressed in between opens the menu bar or the start menu. Tapping Ctrl prevents that, which is the trick AutoHotkey uses.
+    let needs_mask = held.iter().any(|vk| [VK_LMENU, VK_RMENU, VK_LWIN, VK_RWIN].contains(vk))
+        && !held.iter().any(|vk| [VK_LCONTROL, VK_RCONTROL].contains(vk));
+    if needs_mask {
+        out.extend(key_tap(VK_LCONTROL, msg));
+    }
+    out.extend(held.iter().map(|vk| Input::from_keyboard(&key_event(*vk, false, msg))));
+
+    let mut caps_lock = state.is_toggled(VK_CAPITAL);
+    let toggle_caps = caps_lock && text.chars().any(|c| {
+        matches!(plan_char(c, layout, true), CharPlan::Unicode) && !matches!(plan_char(c, layout, false), CharPlan::Unicode)
+    });
+    if toggle_caps {
+        out.extend(key_tap(VK_CAPITAL, msg));
+        caps_lock = false;
+    }
+    let num_lock = state.is_toggled(VK_NUMLOCK);
+    let plan = |c, out:&mut Vec<Input>| match plan_char(c, layout, caps_lock) {
+        CharPlan::Key(vk_ss) => out.extend(key_press_vk_ss(vk_ss, layout, msg)),
+        CharPlan::DeadKey(dead, base) => {
+            out.extend(key_press_vk_ss(dead, layout, msg));
+            out.extend(key_press_vk_ss(base, layout, msg));
+        },
+        CharPlan::Unicode => out.extend(key_fallback(c, options.fallback, num_lock, msg)),
+    };
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' | '\n' => {
+                if c == '\r' && options.collapse_crlf {
+                    chars.next_if_eq(&'\n');
+                }
+                out.extend(key_press_vk_ss((VK_RETURN, options.newline.shift_state()), layout, msg));
+            },
+            '\t' => match options.tab {
+                TabPolicy::Key => plan(c, out),
+                TabPolicy::Spaces(n) => (0..n).for_each(|_| plan(' ', out)),
+                TabPolicy::Unicode => out.extend(key_unicode(c, msg)),
+            },
+            c if c.is_control() && options.control_chars == ControlCharPolicy::Drop => (),
+            c => plan(c, out),
+        }
+    }
+    if toggle_caps {
+        out.extend(key_tap(VK_CAPITAL, msg));
+    }
+
+    out.extend(held.iter().map(|vk| Input::from_keyboard(&key_event(*vk, true, msg))));
+    if needs_mask {
+        out.extend(key_tap(VK_LCONTROL, msg));
+    }
+}
+
+/// Plans every character of `text` as unicode, without looking at the layout's keys, except for line breaks, which are pressed as `options.newline` says since programs rarely take a unicode line break as Enter. Control characters are dropped if `options.control_chars` says to.
+pub fn inputs_for_text_unicode(
+    text: &str,
+    layout: &KeyboardLayout,
+    options: &TextOptions,
+    out: &mut Vec<Input>
+) {
+    let msg = options.msg;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' | '\n' => {
+                if c == '\r' && options.collapse_crlf {
+                    chars.next_if_eq(&'\n');
+                }
+                out.extend(key_press_vk_ss((VK_RETURN, options.newline.shift_state()), layout, msg));
+            },
+            c if c.is_control() && options.control_chars == ControlCharPolicy::Drop => (),
+            c => out.extend(key_unicode(c, msg)),
         }
     }
 }
 
 pub fn send_text(
     text: &str,
-) -> io::Result<()> {
+) -> Result<SendReport> {
+    send_text_with_options(text, &TextOptions::default())
+}
+
+/// Types `text` into the foreground window using its layout and the current keyboard state, or pastes it through the system clipboard if `options.paste` says to.
+pub fn send_text_with_options(
+    text: &str,
+    options: &TextOptions,
+) -> Result<SendReport> {
     let layout = KeyboardLayout::current();
-    send_text_with_msg_layout(text, None, &layout)
+    match options.paste {
+        Some(paste) if paste.should_paste(text, &layout) => paste_text(text, &paste, options.msg, &mut default_sink()?, &mut default_clipboard()?),
+        _ => send_text_with_state(text, &layout, &KeyState::current(), options, &mut default_sink()?),
+    }
 }
 
 pub fn send_key(
     key: &str,
     key_down: bool,
-) -> io::Result<()> {
+) -> Result<SendReport> {
     let layout = KeyboardLayout::current();
-    send_key_layout(key, key_down, &layout)
+    send_key_with_state(key, key_down, &layout, &KeyState::current(), &mut default_sink()?)
 }
 
-pub fn send_key_layout(
+/// Sends the key assuming that Num Lock is on. See `send_key_with_state`.
+pub fn send_key_layout<S: InputSink>(
     key: &str,
     key_down: bool,
     layout: &KeyboardLayout,
-) -> io::Result<()> {
-    let inputs = vec![input_for_key(key, key_down, layout)];
-    dbg!(crate::input::send_input(&inputs)).map(|count| assert_eq!(inputs.len() as u32, count))
+    sink: &mut S,
+) -> Result<SendReport> {
+    send_key_with_state(key, key_down, layout, &KeyState::default().with_toggled(VK_NUMLOCK), sink)
 }
 
-pub fn input_for_key<'a>(
-    key: &'a str,
+pub fn send_key_with_state<S: InputSink, K: KeyStateSource>(
+    key: &str,
+    key_down: bool,
+    layout: &KeyboardLayout,
+    state: &K,
+    sink: &mut S,
+) -> Result<SendReport> {
+    let mut inputs = vec![];
+    inputs_for_key_with_state(key, key_down, layout, state, &mut inputs)?;
+    send_all(&inputs, 0, sink)
+}
+
+/// Presses and releases a combo like "ctrl+shift+t" in the foreground window. See `combo::Combo::parse`.
+pub fn send_combo(
+    combo: &str,
+) -> Result<SendReport> {
+    send_combo_layout(combo, &KeyboardLayout::current(), &mut default_sink()?)
+}
+
+pub fn send_combo_layout<S: InputSink>(
+    combo: &str,
+    layout: &KeyboardLayout,
+    sink: &mut S,
+) -> Result<SendReport> {
+    send_all(&Combo::parse(combo, layout)?.inputs(None), 0, sink)
+}
+
+/// Sends a combo in Plover's syntax, like "alt_l(tab tab) control_l(c)", to the foreground window. See `combo::parse_plover_combo`.
+pub fn send_plover_combo(
+    combo: &str,
+) -> Result<SendReport> {
+    send_plover_combo_layout(combo, &KeyboardLayout::current(), &mut default_sink()?)
+}
+
+pub fn send_plover_combo_layout<S: InputSink>(
+    combo: &str,
+    layout: &KeyboardLayout,
+    sink: &mut S,
+) -> Result<SendReport> {
+    send_all(&plover_combo_inputs(combo, layout, None)?, 0, sink)
+}
+
+/// Runs an AutoHotkey `Send` string, like "^c" or "{LWin down}r{LWin up}", in the foreground window. See `ahk::parse`.
+pub fn send_ahk(
+    s: &str,
+) -> Result<SendReport> {
+    let layout = KeyboardLayout::current();
+    send_ahk_with_state(s, &layout, &KeyState::current(), &TextOptions::default(), &mut default_sink()?)
+}
+
+/// Nothing is sent if `s` doesn't parse.
+pub fn send_ahk_with_state<S: InputSink, K: KeyStateSource>(
+    s: &str,
+    layout: &KeyboardLayout,
+    state: &K,
+    options: &TextOptions,
+    sink: &mut S,
+) -> Result<SendReport> {
+    let mut inputs = vec![];
+    inputs_for_ahk(&parse_ahk(s)?, layout, state, options, &mut inputs)?;
+    send_all(&inputs, options.retries, sink)
+}
+
+/// Parses and runs a macro script in the foreground window. See `script`.
+pub fn send_script(
+    source: &str,
+) -> Result<SendReport> {
+    let layout = KeyboardLayout::current();
+    let timeline = Script::parse(source, &layout)?.compile(&layout, &KeyState::current(), &TextOptions::default());
+    timeline.run(0, &mut default_sink()?, &mut SystemClock::new())
+}
+
+fn mouse_move(m: MouseMovement) -> Input {
+    Input::from_mouse(&MouseInput{e: MouseInputEnum::Move{m, coalesce: false}, msg: None, time: None})
+}
+
+/// Moves the mouse onto the pixel `x`, `y` of the virtual desktop, where 0,0 is the top-left of the primary monitor. See `monitor::Monitors::move_to_pixel`.
+pub fn move_to_pixel(
+    x: i32,
+    y: i32,
+) -> Result<SendReport> {
+    move_to_pixel_with_monitors(&Monitors::current()?, x, y, &mut default_sink()?)
+}
+
+pub fn move_to_pixel_with_monitors<S: InputSink>(
+    monitors: &Monitors,
+    x: i32,
+    y: i32,
+    sink: &mut S,
+) -> Result<SendReport> {
+    send_all(&[mouse_move(monitors.move_to_pixel(x, y)?)], 0, sink)
+}
+
+/// Moves the mouse onto the pixel `x`, `y` counting from the top-left of a monitor, where monitor 0 is the primary one. See `monitor::Monitors::move_to_monitor_relative`.
+pub fn move_to_monitor_relative(
+    monitor: usize,
+    x: i32,
+    y: i32,
+) -> Result<SendReport> {
+    move_to_monitor_relative_with_monitors(&Monitors::current()?, monitor, x, y, &mut default_sink()?)
+}
+
+pub fn move_to_monitor_relative_with_monitors<S: InputSink>(
+    monitors: &Monitors,
+    monitor: usize,
+    x: i32,
+    y: i32,
+    sink: &mut S,
+) -> Result<SendReport> {
+    send_all(&[mouse_move(monitors.move_to_monitor_relative(monitor, x, y)?)], 0, sink)
+}
+
+/// # Panics
+///
+/// Panics if the layout has no key called `key`. See `try_input_for_key`.
+pub fn input_for_key(
+    key: &str,
     key_down: bool,
     layout: &KeyboardLayout,
 ) -> Input {
+    try_input_for_key(key, key_down, layout).unwrap()
+}
+
+/// Like `input_for_key`, but fails with `Error::UnknownKeyName` if the layout has no key called `key`.
+pub fn try_input_for_key<'a>(
+    key: &'a str,
+    key_down: bool,
+    layout: &KeyboardLayout,
+) -> Result<Input> {
     let key_borrow_garbage:Cow<'a, str> = key.into();
-    let vk = layout.keyname_to_vk().get(&key_borrow_garbage).unwrap();
+    let vk = layout.keyname_to_vk().get(&key_borrow_garbage).ok_or_else(|| Error::UnknownKeyName(key.into()))?;
     let ki = key_event(*vk, key_down, None);
-    Input::from_keyboard(&ki.into())
-}
\ No newline at end of file
+    Ok(Input::from_keyboard(&ki))
+}
+
+/// Like `try_input_for_key`, but the keypad digits and decimal point (`kp_0` to `kp_9`, `kp_decimal`) are pressed with Num Lock turned on for just that key if it's off, since otherwise the keypad types arrows and the like.
+pub fn inputs_for_key_with_state<K: KeyStateSource>(
+    key: &str,
+    key_down: bool,
+    layout: &KeyboardLayout,
+    state: &K,
+    out: &mut Vec<Input>,
+) -> Result<()> {
+    let input = try_input_for_key(key, key_down, layout)?;
+    let needs_num_lock = match input.as_keyboard().map(|ki| ki.e) {
+        Some(KeyboardInputEnum::VirtualKeyCode{code, ..}) => {
+            (VK_NUMPAD0 as u16..=VK_NUMPAD9 as u16).contains(&code.0) || code.0 == VK_DECIMAL as u16
+        },
+        _ => false,
+    };
+    if key_down && needs_num_lock && !state.is_toggled(VK_NUMLOCK) {
+        out.extend(key_tap(VK_NUMLOCK, None));
+        out.push(input);
+        out.extend(key_tap(VK_NUMLOCK, None));
+    } else {
+        out.push(input);
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! A virtual keyboard that turns `Input`s back into the text they would type, so that what `send` plans can be checked without windows.
+
+use std::collections::HashSet;
+
+use crate::input::{Input, KeyboardInput, KeyboardInputEnum};
+use crate::key_state::KeyStateSource;
+use crate::layout::{KeyboardLayout, CodePage, CP437, CP1252, SHIFT_STATE_SHIFT, SHIFT_STATE_CTRL, SHIFT_STATE_MENU, SHIFT_STATE_ALTGR};
+use crate::layout::maps::*;
+use crate::sink::InputSink;
+
+/// Tracks which keys are down and any pending dead key, and collects the text that a focused text box would receive, the way ToUnicodeEx works it out.
+///
+/// Virtual key events are looked up in the layout using the shift state made from the modifier keys that are down, with right Alt counting as Ctrl+Alt on layouts with AltGr. Caps Lock, Num Lock and Scroll Lock toggle when pressed. Keypad digits only type while Num Lock is on, like on a real keypad. Holding Alt and typing a code on the keypad types that character, in CP1252 with a leading zero and CP437 without, like US English windows does, or as a hex code point after keypad plus. Unicode events are passed through, with surrogate pairs joined back together. Scan code, mouse and hardware events are ignored.
+#[derive(Debug)]
+pub struct KeyboardSimulator<'a> {
+    layout: &'a KeyboardLayout,
+    down: HashSet<u8>,
+    toggled: HashSet<u8>,
+    dead_char: Option<char>,
+    high_surrogate: Option<u16>,
+    /// The digits of an Alt+Numpad code typed so far, starting with "+" for hex codes
+    alt_code: Option<String>,
+    text: String,
+}
+
+impl<'a> KeyboardSimulator<'a> {
+    pub fn new(layout: &'a KeyboardLayout) -> Self {
+        Self{
+            layout,
+            down: HashSet::new(),
+            toggled: HashSet::new(),
+            dead_char: None,
+            high_surrogate: None,
+            alt_code: None,
+            text: String::new(),
+        }
+    }
+
+    /// The text typed so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the text typed so far and starts over with an empty string. Keys that are down stay down.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn is_down(&self, vk: u8) -> bool {
+        self.down.contains(&vk)
+    }
+
+    /// Turns a toggle key (`VK_CAPITAL`, `VK_NUMLOCK` or `VK_SCROLL`) on or off without pressing it, eg to start with Caps Lock on.
+    pub fn set_toggled(&mut self, vk: u8, on: bool) {
+        if on {
+            self.toggled.insert(vk);
+        } else {
+            self.toggled.remove(&vk);
+        }
+    }
+
+    pub fn is_toggled(&self, vk: u8) -> bool {
+        self.toggled.contains(&vk)
+    }
+
+    /// The dead key waiting for the next character, if any.
+    pub fn pending_dead_char(&self) -> Option<char> {
+        self.dead_char
+    }
+
+    /// The shift state made by the modifier keys that are currently down.
+    pub fn shift_state(&self) -> u8 {
+        let mut ss = 0;
+        for (state, vks) in [
+            (SHIFT_STATE_SHIFT, [VK_SHIFT,   VK_LSHIFT,   VK_RSHIFT]),
+            (SHIFT_STATE_CTRL,  [VK_CONTROL, VK_LCONTROL, VK_RCONTROL]),
+            (SHIFT_STATE_MENU,  [VK_MENU,    VK_LMENU,    VK_RMENU]),
+        ] {
+            if vks.iter().any(|vk| self.is_down(*vk)) {
+                ss |= state;
+            }
+        }
+        if self.layout.has_altgr() && self.is_down(VK_RMENU) {
+            ss |= SHIFT_STATE_ALTGR;
+        }
+        ss
+    }
+
+    pub fn feed(&mut self, input: &Input) {
+        if let Some(ki) = input.as_keyboard() {
+            self.feed_keyboard(ki);
+        }
+    }
+
+    pub fn feed_all<'i>(&mut self, inputs: impl IntoIterator<Item = &'i Input>) {
+        for input in inputs {
+            self.feed(input);
+        }
+    }
+
+    pub fn feed_keyboard(&mut self, ki: &KeyboardInput) {
+        match ki.e {
+            KeyboardInputEnum::VirtualKeyCode{code, ..} => {
+                let Ok(vk) = u8::try_from(code.0) else { return };
+                if ki.key_up {
+                    self.down.remove(&vk);
+                    if ![VK_MENU, VK_LMENU, VK_RMENU].iter().any(|vk| self.is_down(*vk)) {
+                        self.finish_alt_code();
+                    }
+                } else {
+                    self.down.insert(vk);
+                    self.key_down(vk);
+                }
+            },
+            KeyboardInputEnum::UnicodeCodeUnit(unit) => {
+                if !ki.key_up {
+                    self.unicode_down(unit);
+                }
+            },
+            KeyboardInputEnum::ScanCode{..} => (),
+        }
+    }
+
+    fn key_down(&mut self, vk: u8) {
+        if [VK_CAPITAL, VK_NUMLOCK, VK_SCROLL].contains(&vk) {
+            let on = !self.is_toggled(vk);
+            self.set_toggled(vk, on);
+            return;
+        }
+        if self.alt_code_key(vk) {
+            return;
+        }
+        if (VK_NUMPAD0..=VK_NUMPAD9).contains(&vk) {
+            if self.is_toggled(VK_NUMLOCK) {
+                self.text.push(char::from(b'0' + vk - VK_NUMPAD0));
+            }
+            return;
+        }
+        let ss = self.shift_state();
+        let caps_char = self.is_toggled(VK_CAPITAL).then(|| self.layout.vk_ss_to_char_caps().get(&(vk, ss))).flatten();
+        if let Some(dead) = self.layout.vk_ss_to_dead_char().get(&(vk, ss)).copied() {
+            match self.dead_char.take() {
+                // Without a composition for the two dead keys, both come out as their plain characters
+                Some(prev) => {
+                    self.text.push(prev);
+                    self.text.push(dead);
+                },
+                None => self.dead_char = Some(dead),
+            }
+        } else if let Some(c) = caps_char.or_else(|| self.layout.vk_ss_to_char().get(&(vk, ss))).copied() {
+            match self.dead_char.take() {
+                Some(dead) => match self.layout.dead_key_compositions().get(&(dead, c)) {
+                    Some(composed) => self.text.push(*composed),
+                    // Windows always lets you type the dead key's character with space, even if the layout doesn't say so
+                    None if c == ' ' => self.text.push(dead),
+                    None => {
+                        self.text.push(dead);
+                        self.text.push(c);
+                    },
+                },
+                None => self.text.push(c),
+            }
+        }
+    }
+
+    /// Collects the key into the Alt+Numpad code if one is being typed, returning whether it did.
+    fn alt_code_key(&mut self, vk: u8) -> bool {
+        if ![VK_MENU, VK_LMENU].iter().any(|vk| self.is_down(*vk)) || self.shift_state() != SHIFT_STATE_MENU {
+            self.alt_code = None;
+            return false;
+        }
+        let digit = if (VK_NUMPAD0..=VK_NUMPAD9).contains(&vk) && self.is_toggled(VK_NUMLOCK) {
+            Some(char::from(b'0' + vk - VK_NUMPAD0))
+        } else if (VK_A..VK_A + 6).contains(&vk) && self.alt_code.as_ref().is_some_and(|code| code.starts_with('+')) {
+            Some(char::from(b'a' + vk - VK_A))
+        } else {
+            None
+        };
+        match (vk, digit) {
+            (VK_ADD, _) if self.alt_code.is_none() => self.alt_code = Some("+".into()),
+            (_, Some(d)) => self.alt_code.get_or_insert_with(String::new).push(d),
+            (VK_MENU | VK_LMENU, _) => (),
+            _ => {
+                self.alt_code = None;
+                return false;
+            },
+        }
+        true
+    }
+
+    fn finish_alt_code(&mut self) {
+        let Some(code) = self.alt_code.take() else { return };
+        let c = if let Some(hex) = code.strip_prefix('+') {
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        } else {
+            let code_page:&CodePage = if code.starts_with('0') { &CP1252 } else { &CP437 };
+            code.parse::<u32>().ok().map(|n| code_page.decode(n as u8))
+        };
+        self.text.extend(c);
+    }
+
+    fn unicode_down(&mut self, unit: u16) {
+        if let Some(high) = self.high_surrogate.take() {
+            if (0xDC00..0xE000).contains(&unit) {
+                let c = char::decode_utf16([high, unit]).next().unwrap().unwrap();
+                self.text.push(c);
+                return;
+            }
+            self.text.push(char::REPLACEMENT_CHARACTER);
+        }
+        if (0xD800..0xDC00).contains(&unit) {
+            self.high_surrogate = Some(unit);
+        } else {
+            self.text.push(char::from_u32(unit.into()).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+    }
+}
+
+impl KeyStateSource for KeyboardSimulator<'_> {
+    fn is_toggled(&self, vk: u8) -> bool {
+        self.is_toggled(vk)
+    }
+
+    fn is_down(&self, vk: u8) -> bool {
+        self.is_down(vk)
+    }
+}
+
+/// Lets the simulator stand in for a real sink, eg with `send::send_text_with_msg_layout`. Every input is accepted.
+impl InputSink for KeyboardSimulator<'_> {
+    fn send(&mut self, inputs: &[Input]) -> crate::Result<u32> {
+        self.feed_all(inputs);
+        Ok(inputs.len() as u32)
+    }
+}
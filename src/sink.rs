@@ -0,0 +1,168 @@
+//! Places that a batch of `Input`s can be sent to.
+
+use crate::{Error, Result};
+use crate::input::{HeldKeys, Input};
+
+/// Something that accepts batches of `Input`s, most commonly by injecting them into the OS.
+pub trait InputSink {
+    /// Sends all of `inputs`, in order. Returns the number of inputs that were accepted, which may be less than `inputs.len()` if the batch was only partially inserted.
+    fn send(&mut self, inputs: &[Input]) -> Result<u32>;
+}
+
+impl<S: InputSink + ?Sized> InputSink for &mut S {
+    fn send(&mut self, inputs: &[Input]) -> Result<u32> {
+        (**self).send(inputs)
+    }
+}
+
+impl<S: InputSink + ?Sized> InputSink for Box<S> {
+    fn send(&mut self, inputs: &[Input]) -> Result<u32> {
+        (**self).send(inputs)
+    }
+}
+
+/// What happened when `send_all` sent a batch. The report for a batch that wasn't all accepted comes back in `Error::PartialSend`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct SendReport {
+    /// How many inputs from the start of the batch were accepted
+    pub accepted: usize,
+    pub total: usize,
+    /// How many times the rest of the batch was sent again after the first try
+    pub retries: u32,
+    /// Key-ups that were sent afterwards for keys the accepted inputs left down
+    pub released: Vec<Input>,
+    /// Keys that are still down because their key-ups weren't accepted either
+    pub stuck: Vec<Input>,
+}
+
+impl SendReport {
+    pub fn is_complete(&self) -> bool {
+        self.accepted == self.total
+    }
+
+    /// The index of the last input that was accepted, if any were.
+    pub fn last_accepted(&self) -> Option<usize> {
+        self.accepted.checked_sub(1)
+    }
+}
+
+/// Sends `inputs`, sending whatever wasn't accepted again up to `retries` times.
+///
+/// A sink can accept just the start of a batch, eg when UIPI blocks input to a window that's more privileged than us. If the batch still isn't all sent after the retries, key-ups are sent for any keys the accepted part left down, so that modifiers don't stay stuck, and `Error::PartialSend` is returned with the report. If nothing at all was accepted on the first try, the sink's error is returned as is; after that, errors just count as nothing being accepted.
+pub fn send_all<S: InputSink + ?Sized>(
+    inputs: &[Input],
+    retries: u32,
+    sink: &mut S,
+) -> Result<SendReport> {
+    let mut report = SendReport{
+        accepted: 0,
+        total: inputs.len(),
+        retries: 0,
+        released: vec![],
+        stuck: vec![],
+    };
+    if inputs.is_empty() {
+        return Ok(report);
+    }
+    loop {
+        match sink.send(&inputs[report.accepted..]) {
+            Ok(count) => report.accepted = (report.accepted + count as usize).min(inputs.len()),
+            Err(e) if report.accepted == 0 && report.retries == 0 => return Err(e),
+            Err(_) => (),
+        }
+        if report.is_complete() || report.retries >= retries {
+            break;
+        }
+        report.retries += 1;
+    }
+    if !report.is_complete() {
+        let mut held = HeldKeys::new();
+        held.track_all(&inputs[..report.accepted]);
+        let key_ups = held.key_ups();
+        let released = sink.send(&key_ups).map_or(0, |count| count as usize).min(key_ups.len());
+        report.released = key_ups[..released].to_vec();
+        report.stuck = key_ups[released..].to_vec();
+        return Err(Error::PartialSend(report));
+    }
+    Ok(report)
+}
+
+/// The sink that `send::send_text` and friends use, which on windows is `SendInputSink`.
+#[cfg(windows)]
+pub fn default_sink() -> Result<Box<dyn InputSink>> {
+    Ok(Box::new(SendInputSink))
+}
+
+/// The sink that `send::send_text` and friends use, which on linux is `uinput::SharedUinputSink`.
+#[cfg(target_os = "linux")]
+pub fn default_sink() -> Result<Box<dyn InputSink>> {
+    Ok(Box::new(crate::uinput::SharedUinputSink))
+}
+
+/// The sink that `send::send_text` and friends use. There isn't one for this platform, so this always fails.
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn default_sink() -> Result<Box<dyn InputSink>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "There is no input backend for this platform").into())
+}
+
+/// Injects inputs using the windows SendInput function.
+#[cfg(windows)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SendInputSink;
+
+#[cfg(windows)]
+impl InputSink for SendInputSink {
+    fn send(&mut self, inputs: &[Input]) -> Result<u32> {
+        crate::input::send_input(inputs)
+    }
+}
+
+/// Accepts everything and keeps it in memory, so that what would have been sent can be inspected afterwards.
+#[derive(Default)]
+pub struct RecordingSink {
+    inputs: Vec<Input>,
+    batch_lens: Vec<usize>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Every input sent so far, across all batches.
+    pub fn inputs(&self) -> &[Input] {
+        &self.inputs
+    }
+
+    /// The length of each batch, in the order they were sent.
+    pub fn batch_lens(&self) -> &[usize] {
+        &self.batch_lens
+    }
+
+    /// Iterates over each batch that was sent.
+    pub fn batches(&self) -> impl Iterator<Item = &[Input]> {
+        let mut start = 0;
+        self.batch_lens.iter().map(move |len| {
+            let batch = &self.inputs[start..start + len];
+            start += len;
+            batch
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.inputs.clear();
+        self.batch_lens.clear();
+    }
+
+    pub fn into_inputs(self) -> Vec<Input> {
+        self.inputs
+    }
+}
+
+impl InputSink for RecordingSink {
+    fn send(&mut self, inputs: &[Input]) -> Result<u32> {
+        self.inputs.extend_from_slice(inputs);
+        self.batch_lens.push(inputs.len());
+        Ok(inputs.len().try_into().unwrap())
+    }
+}
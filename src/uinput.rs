@@ -0,0 +1,380 @@
+//! Linux backend: plays `Input`s through virtual devices created with `/dev/uinput`.
+//!
+//! Keys are sent as the evdev keycode of the key that produces the virtual key on a US keyboard, so what actually gets typed depends on the keymap that the display server (or console) applies on top.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use crate::input::*;
+use crate::layout::maps::VIRTUAL_CODE_TO_EVDEV;
+use crate::sink::InputSink;
+
+const EV_SYN:u16 = 0x00;
+const EV_KEY:u16 = 0x01;
+const EV_REL:u16 = 0x02;
+const EV_ABS:u16 = 0x03;
+
+const SYN_REPORT:u16 = 0;
+
+const REL_X:u16 = 0x00;
+const REL_Y:u16 = 0x01;
+const REL_HWHEEL:u16 = 0x06;
+const REL_WHEEL:u16 = 0x08;
+const REL_WHEEL_HI_RES:u16 = 0x0b;
+const REL_HWHEEL_HI_RES:u16 = 0x0c;
+
+const ABS_X:u16 = 0x00;
+const ABS_Y:u16 = 0x01;
+
+pub const BTN_LEFT:u16 = 0x110;
+pub const BTN_RIGHT:u16 = 0x111;
+pub const BTN_MIDDLE:u16 = 0x112;
+pub const BTN_SIDE:u16 = 0x113;
+pub const BTN_EXTRA:u16 = 0x114;
+
+const BUS_VIRTUAL:u16 = 0x06;
+
+// These are the _IO/_IOW encodings from linux/uinput.h for architectures using the generic ioctl layout.
+const UI_DEV_CREATE:u64 = 0x5501;
+const UI_DEV_DESTROY:u64 = 0x5502;
+const UI_DEV_SETUP:u64 = 0x405c5503;
+const UI_ABS_SETUP:u64 = 0x401c5504;
+const UI_SET_EVBIT:u64 = 0x40045564;
+const UI_SET_KEYBIT:u64 = 0x40045565;
+const UI_SET_RELBIT:u64 = 0x40045566;
+const UI_SET_ABSBIT:u64 = 0x40045567;
+
+const fn ui_get_sysname(len: usize) -> u64 {
+    (2 << 30) | ((len as u64) << 16) | (0x55 << 8) | 44
+}
+
+/// Absolute coordinates in `MouseMovement` go from 0 to this, which is passed straight through as the range of ABS_X and ABS_Y.
+const ABS_MAX:i32 = 65535;
+
+/// How long to wait after creating devices before sending to them, so that the display server has a chance to open them. Events sent before then are lost.
+pub const SETTLE_TIME:Duration = Duration::from_millis(200);
+
+/// Scan codes that are sent with the E0 prefix, mapped to their evdev keycodes. Without the prefix, set 1 scan codes up to 0x58 are the same number as the evdev keycode.
+static EXTENDED_SCAN_CODES:phf::Map<u16, u16> = phf::phf_map! {
+    0x1Cu16 => 96,  // KEY_KPENTER
+    0x1Du16 => 97,  // KEY_RIGHTCTRL
+    0x35u16 => 98,  // KEY_KPSLASH
+    0x37u16 => 99,  // KEY_SYSRQ
+    0x38u16 => 100, // KEY_RIGHTALT
+    0x47u16 => 102, // KEY_HOME
+    0x48u16 => 103, // KEY_UP
+    0x49u16 => 104, // KEY_PAGEUP
+    0x4Bu16 => 105, // KEY_LEFT
+    0x4Du16 => 106, // KEY_RIGHT
+    0x4Fu16 => 107, // KEY_END
+    0x50u16 => 108, // KEY_DOWN
+    0x51u16 => 109, // KEY_PAGEDOWN
+    0x52u16 => 110, // KEY_INSERT
+    0x53u16 => 111, // KEY_DELETE
+    0x5Bu16 => 125, // KEY_LEFTMETA
+    0x5Cu16 => 126, // KEY_RIGHTMETA
+    0x5Du16 => 127, // KEY_COMPOSE
+};
+
+/// The evdev keycode for a virtual key. `extended` is taken into account the same way windows does for the keys where it picks the right-hand or keypad variant.
+pub fn vk_to_evdev(vk: VirtualKey, extended: bool) -> Option<u16> {
+    use crate::layout::maps::{VK_CONTROL, VK_MENU, VK_RETURN};
+    let vk:u8 = vk.0.try_into().ok()?;
+    match (vk, extended) {
+        (VK_CONTROL, true) => Some(97), // KEY_RIGHTCTRL
+        (VK_MENU, true) => Some(100),   // KEY_RIGHTALT
+        (VK_RETURN, true) => Some(96),  // KEY_KPENTER
+        _ => VIRTUAL_CODE_TO_EVDEV.get(&vk).copied(),
+    }
+}
+
+/// The evdev keycode for a set 1 scan code.
+pub fn scan_code_to_evdev(code: u16, extended: bool) -> Option<u16> {
+    if extended {
+        EXTENDED_SCAN_CODES.get(&code).copied()
+    } else if (1..=0x58).contains(&code) {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+fn mouse_button_to_evdev(button: MouseButton) -> u16 {
+    match button {
+        MouseButton::Left => BTN_LEFT,
+        MouseButton::Right => BTN_RIGHT,
+        MouseButton::Middle => BTN_MIDDLE,
+        MouseButton::X1 => BTN_SIDE,
+        MouseButton::X2 => BTN_EXTRA,
+    }
+}
+
+fn ioctl(file: &File, request: u64, arg: libc::c_ulong) -> io::Result<()> {
+    let res = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg) };
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// One virtual device created through /dev/uinput. It is destroyed when dropped.
+#[derive(Debug)]
+struct UinputDevice {
+    file: File,
+}
+
+impl UinputDevice {
+    fn create(
+        name: &str,
+        keys: impl IntoIterator<Item = u16>,
+        rels: &[u16],
+        abss: &[u16],
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open("/dev/uinput")?;
+
+        ioctl(&file, UI_SET_EVBIT, EV_KEY.into())?;
+        for key in keys {
+            ioctl(&file, UI_SET_KEYBIT, key.into())?;
+        }
+        if !rels.is_empty() {
+            ioctl(&file, UI_SET_EVBIT, EV_REL.into())?;
+            for rel in rels {
+                ioctl(&file, UI_SET_RELBIT, (*rel).into())?;
+            }
+        }
+        if !abss.is_empty() {
+            ioctl(&file, UI_SET_EVBIT, EV_ABS.into())?;
+            for abs in abss {
+                ioctl(&file, UI_SET_ABSBIT, (*abs).into())?;
+                let mut abs_setup:libc::uinput_abs_setup = unsafe { std::mem::zeroed() };
+                abs_setup.code = *abs;
+                abs_setup.absinfo.minimum = 0;
+                abs_setup.absinfo.maximum = ABS_MAX;
+                ioctl(&file, UI_ABS_SETUP, &abs_setup as *const _ as libc::c_ulong)?;
+            }
+        }
+
+        let mut setup:libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        setup.id.vendor = 0x7775; // "wu"
+        setup.id.product = 0x6970; // "ip"
+        setup.id.version = 1;
+        for (dst, src) in setup.name.iter_mut().zip(name.bytes().take(libc::UINPUT_MAX_NAME_SIZE - 1)) {
+            *dst = src as libc::c_char;
+        }
+        ioctl(&file, UI_DEV_SETUP, &setup as *const _ as libc::c_ulong)?;
+        ioctl(&file, UI_DEV_CREATE, 0)?;
+
+        Ok(Self{file})
+    }
+
+    /// The name of the device under /sys/devices/virtual/input/, eg "input17"
+    fn sysname(&self) -> io::Result<String> {
+        let mut buf = [0u8; 64];
+        ioctl(&self.file, ui_get_sysname(buf.len()), buf.as_mut_ptr() as libc::c_ulong)?;
+        let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+        Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+    }
+
+    /// The /dev/input/event* nodes that the kernel made for this device.
+    fn event_nodes(&self) -> io::Result<Vec<PathBuf>> {
+        let sys_dir = PathBuf::from("/sys/devices/virtual/input").join(self.sysname()?);
+        let mut res = vec![];
+        for entry in std::fs::read_dir(sys_dir)? {
+            let name = entry?.file_name();
+            if name.to_string_lossy().starts_with("event") {
+                res.push(PathBuf::from("/dev/input").join(name));
+            }
+        }
+        res.sort();
+        Ok(res)
+    }
+
+    fn emit(&mut self, events: &[(u16, u16, i32)]) -> io::Result<()> {
+        let mut buf = Vec::with_capacity((events.len() + 1) * std::mem::size_of::<libc::input_event>());
+        for (type_, code, value) in events.iter().copied().chain(std::iter::once((EV_SYN, SYN_REPORT, 0))) {
+            let mut ev:libc::input_event = unsafe { std::mem::zeroed() };
+            ev.type_ = type_;
+            ev.code = code;
+            ev.value = value;
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    &ev as *const libc::input_event as *const u8,
+                    std::mem::size_of::<libc::input_event>(),
+                )
+            };
+            buf.extend_from_slice(bytes);
+        }
+        self.file.write_all(&buf)
+    }
+}
+
+impl Drop for UinputDevice {
+    fn drop(&mut self) {
+        let _ = ioctl(&self.file, UI_DEV_DESTROY, 0);
+    }
+}
+
+/// Sends inputs through a virtual keyboard+mouse and a virtual absolute pointer, created through /dev/uinput.
+///
+/// `KeyboardInputEnum::UnicodeCodeUnit` and `Input::Hardware` have no equivalent in evdev, so batches containing them are rejected without sending anything.
+///
+/// Absolute movements cover the whole area that the display server maps the absolute pointer to, which is normally every monitor; `AbsolutePrimaryMonitor` and `AbsoluteVirtualDesktop` both behave like `AbsoluteVirtualDesktop`.
+#[derive(Debug)]
+pub struct UinputSink {
+    keyboard: UinputDevice,
+    pointer: UinputDevice,
+    /// Wheel movement that hasn't yet added up to a whole click, for (vertical, horizontal)
+    wheel_remainder: (i32, i32),
+}
+
+impl UinputSink {
+    /// Creates the virtual devices. Note that the display server takes a moment to notice new devices; see `SETTLE_TIME`.
+    pub fn new() -> io::Result<Self> {
+        let mouse_buttons = [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE, BTN_SIDE, BTN_EXTRA];
+        let mut keys:Vec<u16> = (1..=0xffu16).collect();
+        keys.extend(VIRTUAL_CODE_TO_EVDEV.values().copied().filter(|k| *k > 0xff));
+        keys.extend(mouse_buttons);
+
+        let keyboard = UinputDevice::create(
+            "winput-stuffer keyboard",
+            keys,
+            &[REL_X, REL_Y, REL_WHEEL, REL_HWHEEL, REL_WHEEL_HI_RES, REL_HWHEEL_HI_RES],
+            &[],
+        )?;
+        let pointer = UinputDevice::create(
+            "winput-stuffer pointer",
+            mouse_buttons,
+            &[],
+            &[ABS_X, ABS_Y],
+        )?;
+        Ok(Self{
+            keyboard,
+            pointer,
+            wheel_remainder: (0, 0),
+        })
+    }
+
+    /// The /dev/input/event* nodes of the keyboard+mouse device, for reading back what was sent.
+    pub fn keyboard_event_nodes(&self) -> io::Result<Vec<PathBuf>> {
+        self.keyboard.event_nodes()
+    }
+
+    /// The /dev/input/event* nodes of the absolute pointer device, for reading back what was sent.
+    pub fn pointer_event_nodes(&self) -> io::Result<Vec<PathBuf>> {
+        self.pointer.event_nodes()
+    }
+
+    fn check_supported(input: &Input) -> io::Result<()> {
+        let unsupported = |what: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} can not be sent through uinput", what)));
+        match input {
+            Input::Hardware(_) => unsupported("Hardware input"),
+            Input::Keyboard(KeyboardInput{e: KeyboardInputEnum::UnicodeCodeUnit(_), ..}) => unsupported("Unicode input"),
+            Input::Keyboard(KeyboardInput{e: KeyboardInputEnum::VirtualKeyCode{code, extended}, ..}) => {
+                if vk_to_evdev(*code, *extended).is_none() {
+                    return unsupported(&format!("Virtual key {:#04x}", code.0));
+                }
+                Ok(())
+            },
+            Input::Keyboard(KeyboardInput{e: KeyboardInputEnum::ScanCode{code, extended}, ..}) => {
+                if scan_code_to_evdev(*code, *extended).is_none() {
+                    return unsupported(&format!("Scan code {:#04x}", code));
+                }
+                Ok(())
+            },
+            Input::Mouse(_) => Ok(()),
+        }
+    }
+
+    fn send_one(&mut self, input: &Input) -> io::Result<()> {
+        match input {
+            Input::Keyboard(k) => {
+                let code = match k.e {
+                    KeyboardInputEnum::VirtualKeyCode{code, extended} => vk_to_evdev(code, extended),
+                    KeyboardInputEnum::ScanCode{code, extended} => scan_code_to_evdev(code, extended),
+                    KeyboardInputEnum::UnicodeCodeUnit(_) => None,
+                }.unwrap();
+                self.keyboard.emit(&[(EV_KEY, code, if k.key_up { 0 } else { 1 })])
+            },
+            Input::Mouse(m) => match m.e {
+                MouseInputEnum::Button{which, button_up} => {
+                    self.keyboard.emit(&[(EV_KEY, mouse_button_to_evdev(which), if button_up { 0 } else { 1 })])
+                },
+                MouseInputEnum::Move{m: MouseMovement::Relative{dx, dy}, ..} => {
+                    self.keyboard.emit(&[(EV_REL, REL_X, dx), (EV_REL, REL_Y, dy)])
+                },
+                MouseInputEnum::Move{m: MouseMovement::AbsolutePrimaryMonitor{x, y}, ..} |
+                MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x, y}, ..} => {
+                    self.pointer.emit(&[(EV_ABS, ABS_X, x.into()), (EV_ABS, ABS_Y, y.into())])
+                },
+                MouseInputEnum::Wheel{horizontal, amount} => {
+                    let (hi_res, clicks, remainder) = if horizontal {
+                        (REL_HWHEEL_HI_RES, REL_HWHEEL, &mut self.wheel_remainder.1)
+                    } else {
+                        (REL_WHEEL_HI_RES, REL_WHEEL, &mut self.wheel_remainder.0)
+                    };
+                    // REL_*_HI_RES uses the same 120-per-click units as windows, but the plain axes only take whole clicks
+                    *remainder += amount;
+                    let whole = *remainder / i32::from(WHEEL_DELTA);
+                    *remainder -= whole * i32::from(WHEEL_DELTA);
+                    if whole != 0 {
+                        self.keyboard.emit(&[(EV_REL, clicks, whole), (EV_REL, hi_res, amount)])
+                    } else {
+                        self.keyboard.emit(&[(EV_REL, hi_res, amount)])
+                    }
+                },
+            },
+            Input::Hardware(_) => unreachable!(),
+        }
+    }
+}
+
+impl InputSink for UinputSink {
+    fn send(&mut self, inputs: &[Input]) -> crate::Result<u32> {
+        for input in inputs {
+            Self::check_supported(input)?;
+        }
+        // Once part of the batch has gone out, say how much, so that send_all can let go of any keys it pressed
+        let mut sent = 0;
+        for input in inputs {
+            match self.send_one(input) {
+                Ok(()) => sent += 1,
+                Err(e) if sent == 0 => return Err(e.into()),
+                Err(_) => break,
+            }
+        }
+        Ok(sent)
+    }
+}
+
+lazy_static! {
+    static ref SHARED_SINK: Mutex<Option<UinputSink>> = Mutex::new(None);
+}
+
+/// A handle to one process-wide `UinputSink`, created (and given `SETTLE_TIME` to be noticed) the first time something is sent.
+///
+/// This is what `send::send_text` and friends use, so that they don't create a new device for every call.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SharedUinputSink;
+
+impl InputSink for SharedUinputSink {
+    fn send(&mut self, inputs: &[Input]) -> crate::Result<u32> {
+        let mut guard = SHARED_SINK.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(UinputSink::new()?);
+            std::thread::sleep(SETTLE_TIME);
+        }
+        guard.as_mut().unwrap().send(inputs)
+    }
+}
@@ -1,12 +1,16 @@
+#[cfg(windows)]
 use std::io;
 
+#[cfg(windows)]
 use windows::Win32::Foundation::LPARAM;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging as wm_sys;
 
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
 pub struct WindowMessage(u32);
 
 impl WindowMessage {
+    #[cfg(windows)]
     pub fn register(message: impl AsRef<str>) -> io::Result<Self> {
 
         let res = unsafe { wm_sys::RegisterWindowMessageW(message.as_ref()) };
@@ -17,6 +21,11 @@ impl WindowMessage {
         }
     }
 
+    /// Wraps an already-registered message number, such as one received from another process.
+    pub fn from_raw(message: u32) -> Self {
+        Self(message)
+    }
+
     pub fn into_inner(self) -> u32 {
         self.0
     }
@@ -29,6 +38,7 @@ impl PartialEq<u32> for WindowMessage {
 }
 
 /// So that windowmessages can directly compare against the result of get_extra_info
+#[cfg(windows)]
 impl PartialEq<LPARAM> for WindowMessage {
     fn eq(&self, other: &LPARAM) -> bool {
         let inner:isize = self.0.try_into().unwrap();
@@ -36,6 +46,7 @@ impl PartialEq<LPARAM> for WindowMessage {
     }
 }
 
+#[cfg(windows)]
 pub fn get_extra_info() -> LPARAM {
     unsafe { wm_sys::GetMessageExtraInfo() }
-}
\ No newline at end of file
+}
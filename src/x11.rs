@@ -0,0 +1,337 @@
+//! X11 backend: replays `Input`s with the XTest extension.
+//!
+//! Keys are sent as the X keycode of the physical key (evdev keycode + 8), the same keys the uinput backend would press. Unicode input is typed by looking the character's keysym up in the keymap, or by temporarily mapping it onto a spare keycode if it isn't there, which is the same trick xdotool uses.
+//!
+//! libX11, libXtst and (optionally, for finding the primary monitor) libXrandr are loaded at runtime, so nothing needs to be installed to build with the `x11` feature.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::io;
+use std::os::raw::{c_int, c_ulong};
+
+use x11_dl::xlib::{self, Xlib};
+use x11_dl::xrandr::Xrandr;
+use x11_dl::xtest::Xf86vmode as Xtest;
+
+use crate::input::*;
+use crate::sink::InputSink;
+use crate::uinput::{scan_code_to_evdev, vk_to_evdev};
+
+/// X keycodes are evdev keycodes offset by this much, on any server using the evdev XKB rules (which is all of them on linux, including Xvfb).
+const EVDEV_TO_X_KEYCODE:u16 = 8;
+
+/// A rectangle of the X screen, in pixels. Normalized coordinates map onto it the same way Windows maps them onto its monitors.
+pub type ScreenRect = crate::monitor::Rect;
+
+/// The keysym that XLib uses for a character, see Appendix A of the X11 protocol spec and keysymdef.h
+pub fn char_to_keysym(c: char) -> c_ulong {
+    match c {
+        '\r' | '\n' => 0xff0d, // XK_Return
+        '\t' => 0xff09,        // XK_Tab
+        '\x08' => 0xff08,      // XK_BackSpace
+        '\x1b' => 0xff1b,      // XK_Escape
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as c_ulong,
+        _ => 0x0100_0000 | c as c_ulong,
+    }
+}
+
+/// Sends inputs to an X server using XTestFakeKeyEvent, XTestFakeButtonEvent and XTestFakeMotionEvent.
+///
+/// `Input::Hardware` has no equivalent, so batches containing it are rejected without sending anything.
+pub struct X11Sink {
+    xlib: Xlib,
+    xtest: Xtest,
+    display: *mut xlib::Display,
+    screen: c_int,
+    screen_rect: ScreenRect,
+    primary_rect: ScreenRect,
+    /// Keycodes that had no keysyms when we connected, which we borrow to type characters that aren't in the keymap
+    spare_keycodes: Vec<u8>,
+    /// Which spare keycode each borrowed character has been put on
+    borrowed: HashMap<char, u8>,
+    next_spare: usize,
+    /// The keycode that each pressed unicode character went down on, so the key up goes to the same place even if it's been remapped since.
+    unicode_down: HashMap<char, u8>,
+    /// The first half of a surrogate pair, waiting for the second half (key down, key up)
+    pending_high_surrogate: (Option<u16>, Option<u16>),
+    /// Wheel movement that hasn't yet added up to a whole click, for (vertical, horizontal)
+    wheel_remainder: (i32, i32),
+}
+
+impl std::fmt::Debug for X11Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("X11Sink")
+            .field("display", &self.display)
+            .field("screen_rect", &self.screen_rect)
+            .field("primary_rect", &self.primary_rect)
+            .finish_non_exhaustive()
+    }
+}
+
+fn open_error(e: x11_dl::error::OpenError) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, e.to_string())
+}
+
+impl X11Sink {
+    /// Connects to `display_name` (eg ":0"), or the display in $DISPLAY if it's None.
+    pub fn open(display_name: Option<&str>) -> io::Result<Self> {
+        let xlib = Xlib::open().map_err(open_error)?;
+        let xtest = Xtest::open().map_err(open_error)?;
+
+        let name = display_name.map(CString::new).transpose().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let display = unsafe { (xlib.XOpenDisplay)(name.as_ref().map_or(std::ptr::null(), |n| n.as_ptr())) };
+        if display.is_null() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Could not open X display {:?}", display_name.map_or_else(|| std::env::var("DISPLAY").unwrap_or_default(), str::to_string))));
+        }
+
+        let mut xtest_event_base = 0;
+        let mut xtest_error_base = 0;
+        let mut xtest_major = 0;
+        let mut xtest_minor = 0;
+        let has_xtest = unsafe { (xtest.XTestQueryExtension)(display, &mut xtest_event_base, &mut xtest_error_base, &mut xtest_major, &mut xtest_minor) };
+        if has_xtest == 0 {
+            unsafe { (xlib.XCloseDisplay)(display) };
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "The X server does not support the XTEST extension"));
+        }
+
+        let screen = unsafe { (xlib.XDefaultScreen)(display) };
+        let screen_rect = ScreenRect{
+            x: 0,
+            y: 0,
+            width: unsafe { (xlib.XDisplayWidth)(display, screen) } as u32,
+            height: unsafe { (xlib.XDisplayHeight)(display, screen) } as u32,
+        };
+        let primary_rect = Self::primary_monitor(&xlib, display, screen).unwrap_or(screen_rect);
+
+        let mut sink = Self{
+            xlib,
+            xtest,
+            display,
+            screen,
+            screen_rect,
+            primary_rect,
+            spare_keycodes: vec![],
+            borrowed: HashMap::new(),
+            next_spare: 0,
+            unicode_down: HashMap::new(),
+            pending_high_surrogate: (None, None),
+            wheel_remainder: (0, 0),
+        };
+        sink.spare_keycodes = sink.find_spare_keycodes();
+        Ok(sink)
+    }
+
+    /// The whole X screen, which is what `MouseMovement::AbsoluteVirtualDesktop` covers.
+    pub fn screen_rect(&self) -> ScreenRect {
+        self.screen_rect
+    }
+
+    /// The primary monitor according to XRandR, which is what `MouseMovement::AbsolutePrimaryMonitor` covers. If XRandR isn't available this is the whole screen.
+    pub fn primary_rect(&self) -> ScreenRect {
+        self.primary_rect
+    }
+
+    fn primary_monitor(xlib: &Xlib, display: *mut xlib::Display, screen: c_int) -> Option<ScreenRect> {
+        let xrandr = Xrandr::open().ok()?;
+        let root = unsafe { (xlib.XRootWindow)(display, screen) };
+        let mut count = 0;
+        let monitors = unsafe { (xrandr.XRRGetMonitors)(display, root, xlib::True, &mut count) };
+        if monitors.is_null() {
+            return None;
+        }
+        let list = unsafe { std::slice::from_raw_parts(monitors, count.max(0) as usize) };
+        let res = list.iter().find(|m| m.primary != 0).or_else(|| list.first()).map(|m| ScreenRect{
+            x: m.x,
+            y: m.y,
+            width: m.width as u32,
+            height: m.height as u32,
+        });
+        unsafe { (xrandr.XRRFreeMonitors)(monitors) };
+        res
+    }
+
+    /// The keyboard mapping for `first..first+count`, as (keysyms_per_keycode, keysyms)
+    fn keyboard_mapping(&self, first: u8, count: c_int) -> (usize, Vec<c_ulong>) {
+        let mut per_keycode = 0;
+        let syms = unsafe { (self.xlib.XGetKeyboardMapping)(self.display, first, count, &mut per_keycode) };
+        if syms.is_null() {
+            return (0, vec![]);
+        }
+        let per_keycode = per_keycode.max(0) as usize;
+        let res = unsafe { std::slice::from_raw_parts(syms, per_keycode * count.max(0) as usize) }.to_vec();
+        unsafe { (self.xlib.XFree)(syms.cast()) };
+        (per_keycode, res)
+    }
+
+    fn find_spare_keycodes(&self) -> Vec<u8> {
+        let mut min = 0;
+        let mut max = 0;
+        unsafe { (self.xlib.XDisplayKeycodes)(self.display, &mut min, &mut max) };
+        let (per_keycode, syms) = self.keyboard_mapping(min as u8, max - min + 1);
+        if per_keycode == 0 {
+            return vec![];
+        }
+        syms.chunks_exact(per_keycode)
+            .zip(min..=max)
+            .filter(|(syms, _)| syms.iter().all(|s| *s == 0))
+            .map(|(_, keycode)| keycode as u8)
+            .collect()
+    }
+
+    fn set_keysym(&mut self, keycode: u8, keysym: c_ulong) {
+        let mut syms = [keysym, keysym];
+        unsafe {
+            (self.xlib.XChangeKeyboardMapping)(self.display, keycode.into(), syms.len() as c_int, syms.as_mut_ptr(), 1);
+            (self.xlib.XSync)(self.display, xlib::False);
+        }
+    }
+
+    /// The keycode that types `c` without any modifiers, mapping it onto a spare keycode if there isn't one already.
+    fn keycode_for_char(&mut self, c: char) -> io::Result<u8> {
+        let keysym = char_to_keysym(c);
+        let existing = unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym) };
+        if existing != 0 && self.keyboard_mapping(existing, 1).1.first() == Some(&keysym) {
+            return Ok(existing);
+        }
+        if let Some(keycode) = self.borrowed.get(&c) {
+            return Ok(*keycode);
+        }
+        if self.spare_keycodes.is_empty() {
+            return Err(io::Error::other(format!("{:?} is not in the keymap and there are no spare keycodes to put it on", c)));
+        }
+        // Cycle through the spare keycodes so that a client which hasn't processed the last remapping yet is less likely to see the wrong character
+        let keycode = self.spare_keycodes[self.next_spare % self.spare_keycodes.len()];
+        self.next_spare += 1;
+        self.borrowed.retain(|_, k| *k != keycode);
+        self.borrowed.insert(c, keycode);
+        self.set_keysym(keycode, keysym);
+        Ok(keycode)
+    }
+
+    fn pointer_position(&self) -> (i32, i32) {
+        let root = unsafe { (self.xlib.XRootWindow)(self.display, self.screen) };
+        let (mut root_ret, mut child_ret) = (0, 0);
+        let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+        let mut mask = 0;
+        unsafe { (self.xlib.XQueryPointer)(self.display, root, &mut root_ret, &mut child_ret, &mut root_x, &mut root_y, &mut win_x, &mut win_y, &mut mask) };
+        (root_x, root_y)
+    }
+
+    fn fake_key(&self, keycode: u8, key_up: bool) {
+        unsafe { (self.xtest.XTestFakeKeyEvent)(self.display, keycode.into(), if key_up { xlib::False } else { xlib::True }, xlib::CurrentTime) };
+    }
+
+    fn fake_button(&self, button: u32, button_up: bool) {
+        unsafe { (self.xtest.XTestFakeButtonEvent)(self.display, button, if button_up { xlib::False } else { xlib::True }, xlib::CurrentTime) };
+    }
+
+    fn send_unicode(&mut self, code_unit: u16, key_up: bool) -> io::Result<()> {
+        let pending = if key_up { &mut self.pending_high_surrogate.1 } else { &mut self.pending_high_surrogate.0 };
+        if (0xD800..0xDC00).contains(&code_unit) {
+            *pending = Some(code_unit);
+            return Ok(());
+        }
+        let units:Vec<u16> = pending.take().into_iter().chain(std::iter::once(code_unit)).collect();
+        let c = char::decode_utf16(units).next().and_then(Result::ok).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Unpaired surrogate in unicode input"))?;
+        if key_up {
+            if let Some(keycode) = self.unicode_down.remove(&c) {
+                self.fake_key(keycode, true);
+            }
+        } else {
+            let keycode = self.keycode_for_char(c)?;
+            self.unicode_down.insert(c, keycode);
+            self.fake_key(keycode, false);
+        }
+        Ok(())
+    }
+
+    fn send_one(&mut self, input: &Input) -> io::Result<()> {
+        match input {
+            Input::Keyboard(k) => match k.e {
+                KeyboardInputEnum::VirtualKeyCode{code, extended} => {
+                    let evdev = vk_to_evdev(code, extended).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Virtual key {:#04x} has no X keycode", code.0)))?;
+                    self.fake_key((evdev + EVDEV_TO_X_KEYCODE) as u8, k.key_up);
+                },
+                KeyboardInputEnum::ScanCode{code, extended} => {
+                    let evdev = scan_code_to_evdev(code, extended).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Scan code {:#04x} has no X keycode", code)))?;
+                    self.fake_key((evdev + EVDEV_TO_X_KEYCODE) as u8, k.key_up);
+                },
+                KeyboardInputEnum::UnicodeCodeUnit(code_unit) => self.send_unicode(code_unit, k.key_up)?,
+            },
+            Input::Mouse(m) => match m.e {
+                MouseInputEnum::Button{which, button_up} => {
+                    let button = match which {
+                        MouseButton::Left => 1,
+                        MouseButton::Middle => 2,
+                        MouseButton::Right => 3,
+                        MouseButton::X1 => 8,
+                        MouseButton::X2 => 9,
+                    };
+                    self.fake_button(button, button_up);
+                },
+                MouseInputEnum::Move{m: MouseMovement::Relative{dx, dy}, ..} => {
+                    // XTestFakeRelativeMotionEvent gained a screen argument in libXtst 1.2.4, so which signature we'd be calling depends on what's installed. Moving relative to where the pointer is now works everywhere.
+                    let (x, y) = self.pointer_position();
+                    unsafe { (self.xtest.XTestFakeMotionEvent)(self.display, self.screen, x + dx, y + dy, xlib::CurrentTime) };
+                },
+                MouseInputEnum::Move{m: MouseMovement::AbsolutePrimaryMonitor{x, y}, ..} => {
+                    let (px, py) = self.primary_rect.normalized_to_pixel(x, y);
+                    unsafe { (self.xtest.XTestFakeMotionEvent)(self.display, self.screen, px, py, xlib::CurrentTime) };
+                },
+                MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x, y}, ..} => {
+                    let (px, py) = self.screen_rect.normalized_to_pixel(x, y);
+                    unsafe { (self.xtest.XTestFakeMotionEvent)(self.display, self.screen, px, py, xlib::CurrentTime) };
+                },
+                MouseInputEnum::Wheel{horizontal, amount} => {
+                    // The wheel is buttons 4 (up), 5 (down), 6 (left) and 7 (right), one press per click
+                    let (remainder, positive, negative) = if horizontal {
+                        (&mut self.wheel_remainder.1, 7, 6)
+                    } else {
+                        (&mut self.wheel_remainder.0, 4, 5)
+                    };
+                    *remainder += amount;
+                    let clicks = *remainder / i32::from(WHEEL_DELTA);
+                    *remainder -= clicks * i32::from(WHEEL_DELTA);
+                    let button = if clicks > 0 { positive } else { negative };
+                    for _ in 0..clicks.unsigned_abs() {
+                        self.fake_button(button, false);
+                        self.fake_button(button, true);
+                    }
+                },
+            },
+            Input::Hardware(_) => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+impl InputSink for X11Sink {
+    fn send(&mut self, inputs: &[Input]) -> crate::Result<u32> {
+        if inputs.iter().any(|i| matches!(i, Input::Hardware(_))) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Hardware input can not be sent through XTest").into());
+        }
+        let mut res = Ok(());
+        let mut sent = 0;
+        for input in inputs {
+            res = self.send_one(input);
+            if res.is_err() { break }
+            sent += 1;
+        }
+        unsafe { (self.xlib.XSync)(self.display, xlib::False) };
+        // Once part of the batch has gone out, say how much, so that send_all can let go of any keys it pressed
+        match res {
+            Err(e) if sent == 0 => Err(e.into()),
+            _ => Ok(sent),
+        }
+    }
+}
+
+impl Drop for X11Sink {
+    fn drop(&mut self) {
+        let borrowed:Vec<u8> = self.borrowed.drain().map(|(_, k)| k).collect();
+        for keycode in borrowed {
+            self.set_keysym(keycode, 0);
+        }
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
@@ -0,0 +1,125 @@
+use winput_stuffer::{Error, KeyboardLayout};
+use winput_stuffer::ahk::{inputs_for_ahk, parse, AhkAction, AhkItem, AhkKey};
+use winput_stuffer::input::{Input, KeyboardInput, KeyboardInputEnum};
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::send::{key_event, send_ahk_with_state, TextOptions};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::sink::RecordingSink;
+
+fn key(vk: u8, down: bool) -> Input {
+    Input::from_keyboard(&key_event(vk, down, None))
+}
+
+fn plan(s: &str) -> Vec<Input> {
+    let mut inputs = vec![];
+    inputs_for_ahk(&parse(s).unwrap(), &KeyboardLayout::us(), &KeyState::default(), &TextOptions::default(), &mut inputs).unwrap();
+    inputs
+}
+
+fn typed(s: &str) -> String {
+    let us = KeyboardLayout::us();
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(&plan(s));
+    sim.take_text()
+}
+
+/// The (line, column, message) of a syntax error
+fn syntax_error(s: &str) -> (usize, usize, String) {
+    match parse(s) {
+        Err(Error::Syntax{line, column, message}) => (line, column, message),
+        other => panic!("expected a syntax error for {:?}, got {:?}", s, other),
+    }
+}
+
+#[test]
+fn modifiers_apply_to_the_next_key() {
+    assert_eq!(plan("^c"), vec![key(VK_LCONTROL, true), key(VK_C, true), key(VK_C, false), key(VK_LCONTROL, false)]);
+    assert_eq!(plan("+{Tab 3}"), vec![
+        key(VK_LSHIFT, true),
+        key(VK_TAB, true), key(VK_TAB, false),
+        key(VK_TAB, true), key(VK_TAB, false),
+        key(VK_TAB, true), key(VK_TAB, false),
+        key(VK_LSHIFT, false),
+    ]);
+    assert_eq!(parse("^!{Delete}x").unwrap(), vec![
+        AhkItem::Key{modifiers: vec![VK_LCONTROL, VK_LMENU], key: AhkKey::Vk(VK_DELETE), action: AhkAction::Press(1)},
+        AhkItem::Text("x".into()),
+    ]);
+    assert_eq!(typed("+ab"), "Ab");
+}
+
+#[test]
+fn keys_down_and_up() {
+    assert_eq!(plan("{LWin down}r{LWin up}"), vec![
+        key(VK_LWIN, true), key(VK_R, true), key(VK_R, false), key(VK_LWIN, false),
+    ]);
+    assert_eq!(plan("{a down}{A up}"), vec![key(VK_A, true), key(VK_A, false)]);
+    assert_eq!(plan("{Enter}{esc}{BS 2}{PgDn}{vk41}{vk41sc01E}"), vec![
+        key(VK_RETURN, true), key(VK_RETURN, false),
+        key(VK_ESCAPE, true), key(VK_ESCAPE, false),
+        key(VK_BACK, true), key(VK_BACK, false), key(VK_BACK, true), key(VK_BACK, false),
+        key(VK_NEXT, true), key(VK_NEXT, false),
+        key(VK_A, true), key(VK_A, false),
+        key(VK_A, true), key(VK_A, false),
+    ]);
+    let scan_code = |key_up| Input::from_keyboard(&KeyboardInput{
+        e: KeyboardInputEnum::ScanCode{code: 0x1d, extended: true}, key_up, msg: None, time: None,
+    });
+    assert_eq!(plan("{sc11D}"), vec![scan_code(false), scan_code(true)]);
+}
+
+#[test]
+fn literal_text() {
+    assert_eq!(typed("Hello, world{!}"), "Hello, world!");
+    assert_eq!(typed("{{}{}}{+}{^}{#}"), "{}+^#");
+    assert_eq!(typed("{Raw}^c{Enter}+!"), "^c{Enter}+!");
+    assert_eq!(typed("ab{TEXT}{x}\n"), "ab{x}\r");
+    assert_eq!(typed("{Blind}a{Space}b"), "a b");
+    assert_eq!(typed("{U+263A}{u+1F600 2}"), "☺😀😀");
+    assert_eq!(typed("x}"), "x}");
+
+    let mut sink = RecordingSink::new();
+    send_ahk_with_state("{ASC 0233}", &KeyboardLayout::us(), &KeyState::default().with_toggled(VK_NUMLOCK), &TextOptions::default(), &mut sink).unwrap();
+    let us = KeyboardLayout::us();
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.set_toggled(VK_NUMLOCK, true);
+    sim.feed_all(sink.inputs());
+    assert_eq!(sim.text(), "é");
+}
+
+#[test]
+fn errors_say_where() {
+    assert_eq!(syntax_error("ab{Nope}"), (1, 4, "unknown key name \"Nope\"".into()));
+    assert_eq!(syntax_error("ab{Tab"), (1, 3, "\"{\" without a closing \"}\"".into()));
+    assert_eq!(syntax_error("{"), (1, 1, "\"{\" without a closing \"}\"".into()));
+    assert_eq!(syntax_error("x^+"), (1, 2, "modifiers at the end have nothing to apply to".into()));
+    assert_eq!(syntax_error("é\nxy{Tab sideways}").0, 2);
+    assert_eq!(syntax_error("é\nxy{Tab sideways}").1, 3);
+    assert_eq!(syntax_error("a{Blind}").2, "{Blind} has to be at the start");
+    assert_eq!(syntax_error("^{Raw}a").1, 1);
+    assert_eq!(syntax_error("{ASC x}").2, "{ASC} needs a decimal code, not \"x\"");
+    assert_eq!(syntax_error("{vk00}").2, "unknown key name \"vk00\"");
+    assert_eq!(syntax_error("{U+D800}").2, "unknown key name \"U+D800\"");
+    assert_eq!(syntax_error("ab{Tab  4000000000}"), (1, 9, "a key can be pressed at most 10000 times at once".into()));
+    assert_eq!(plan("{Tab 10000}").len(), 20000);
+}
+
+#[test]
+fn unsupported_constructs_are_errors() {
+    for (s, message) in [
+        ("{Click}", "the mouse ({Click}) isn't supported"),
+        ("a{WheelDown 3}", "the mouse ({WheelDown}) isn't supported"),
+        ("{LButton}", "the mouse ({LButton}) isn't supported"),
+        ("{Ctrl DownR}", "{Ctrl DownR} isn't supported"),
+        ("{U+263A down}", "{U+263A down} isn't supported"),
+    ] {
+        assert_eq!(syntax_error(s).2, message, "{:?}", s);
+    }
+
+    // Nothing is sent
+    let mut sink = RecordingSink::new();
+    let res = send_ahk_with_state("abc{Click}", &KeyboardLayout::us(), &KeyState::default(), &TextOptions::default(), &mut sink);
+    assert!(matches!(res, Err(Error::Syntax{..})));
+    assert!(sink.inputs().is_empty());
+}
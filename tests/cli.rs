@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary with the bundled US layout, so the output doesn't depend on the machine
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_winput-stuffer"))
+        .args(["--layout", "00000409"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn dry_run(args: &[&str]) -> String {
+    let output = run(&[&["--dry-run"], args].concat(), "");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn typing() {
+    assert_eq!(dry_run(&["type", "Hi", "a"]), "send SHIFT down, H down, H up, SHIFT up, I down, I up, SPACE down, SPACE up, A down, A up\n");
+    assert_eq!(dry_run(&["--strategy", "unicode", "type", "a"]), "send U+0061 down, U+0061 up\n");
+    assert_eq!(dry_run(&["--strategy", "paste", "--delay", "2s", "type", "ab"]), "sleep 2s\npaste \"ab\"\nsend LCONTROL down, V down, V up, LCONTROL up\n");
+    assert_eq!(dry_run(&["--rate", "4", "type", "abc"]), "send A down, A up\nsleep 250ms\nsend B down, B up\nsleep 250ms\nsend C down, C up\n");
+
+    let output = run(&["--dry-run", "type", "--file", "-"], "x\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "send X down, X up, RETURN down, RETURN up\n");
+
+    // Options after the command are text to type
+    assert_eq!(dry_run(&["type", "-h"]), dry_run(&["--", "type", "-h"]));
+    assert_eq!(dry_run(&["type", "--rate", "5"]), "send OEM_MINUS down, OEM_MINUS up, OEM_MINUS down, OEM_MINUS up, R down, R up, A down, A up, T down, T up, E down, E up, SPACE down, SPACE up, DIGIT5 down, DIGIT5 up\n");
+}
+
+#[test]
+fn keys_and_mouse() {
+    assert_eq!(dry_run(&["key", "f5"]), "send F5 down, F5 up\n");
+    assert_eq!(dry_run(&["key", "shift_l", "--down"]), "send LSHIFT down\n");
+    assert_eq!(dry_run(&["combo", "ctrl+alt+t"]), "send LCONTROL down, LMENU down, T down, T up, LMENU up, LCONTROL up\n");
+    assert_eq!(dry_run(&["mouse", "move", "10", "-5"]), "send move by 10,-5\n");
+    assert_eq!(dry_run(&["mouse", "click"]), "send left button down, left button up\n");
+    assert_eq!(dry_run(&["mouse", "scroll", "2"]), "send wheel 240\n");
+}
+
+#[test]
+fn layout_dump() {
+    let dump = dry_run(&["layout", "dump"]);
+    assert!(dump.starts_with("layout 04090409\n"));
+    assert!(dump.contains("'A'\tSHIFT+A\n"));
+}
+
+#[test]
+fn errors() {
+    let output = run(&["frobnicate"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown command \"frobnicate\""));
+
+    let output = run(&["--dry-run", "combo", "ctrl+nope"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "winput-stuffer: Unknown key name \"nope\"\n");
+
+    assert_eq!(run(&["--rate", "0", "type", "a"], "").status.code(), Some(2));
+    for rate in ["1e-300", "inf", "NaN"] {
+        let output = run(&["--dry-run", "--rate", rate, "type", "a"], "");
+        assert_eq!(output.status.code(), Some(2), "--rate {}", rate);
+    }
+}
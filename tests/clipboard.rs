@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use winput_stuffer::{Error, KeyboardLayout};
+use winput_stuffer::clipboard::MemoryClipboard;
+use winput_stuffer::input::{Input, KeyboardInputEnum};
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::pace::FakeClock;
+use winput_stuffer::send::{paste_text, paste_text_with_clock, send_text_with_clipboard, PasteOptions, PasteShortcut, TextOptions};
+use winput_stuffer::sink::{InputSink, RecordingSink};
+
+fn vk_events(inputs: &[Input]) -> Vec<(u8, bool)> {
+    inputs.iter().map(|i| match i.as_keyboard().unwrap().e {
+        KeyboardInputEnum::VirtualKeyCode{code, ..} => (code.0 as u8, i.as_keyboard().unwrap().key_up),
+        other => panic!("expected a virtual key, got {:?}", other),
+    }).collect()
+}
+
+fn no_delay() -> PasteOptions {
+    PasteOptions{restore_delay: Duration::ZERO, ..Default::default()}
+}
+
+#[test]
+fn paste_restores_the_clipboard() {
+    let mut sink = RecordingSink::new();
+    let mut clipboard = MemoryClipboard::with_text("old");
+    let mut clock = FakeClock::new();
+    paste_text_with_clock("new", &PasteOptions::default(), None, &mut sink, &mut clipboard, &mut clock).unwrap();
+    assert_eq!(vk_events(sink.inputs()), vec![
+        (VK_LCONTROL, false), (VK_V, false), (VK_V, true), (VK_LCONTROL, true),
+    ]);
+    assert_eq!(clipboard.history, vec![Some("new".into()), Some("old".into())]);
+    assert_eq!(clipboard.text.as_deref(), Some("old"));
+    // The target gets time to read the clipboard before it's put back
+    assert_eq!(clock.sleeps, vec![Duration::from_millis(100)]);
+
+    // An empty clipboard is left empty
+    let mut clipboard = MemoryClipboard::default();
+    paste_text("new", &no_delay(), None, &mut sink, &mut clipboard).unwrap();
+    assert_eq!(clipboard.history, vec![Some("new".into()), None]);
+
+    // Anything that isn't text can't be put back, but isn't cleared away either
+    let mut clipboard = MemoryClipboard{other_data: true, ..Default::default()};
+    paste_text("new", &no_delay(), None, &mut sink, &mut clipboard).unwrap();
+    assert_eq!(clipboard.history, vec![Some("new".into())]);
+}
+
+#[test]
+fn paste_shortcuts() {
+    for (shortcut, vks) in [
+        (PasteShortcut::ShiftInsert, vec![VK_LSHIFT, VK_INSERT]),
+        (PasteShortcut::CtrlShiftV, vec![VK_LCONTROL, VK_LSHIFT, VK_V]),
+        (PasteShortcut::Keys(&[VK_RCONTROL, VK_INSERT]), vec![VK_RCONTROL, VK_INSERT]),
+    ] {
+        let mut sink = RecordingSink::new();
+        let options = PasteOptions{shortcut, ..no_delay()};
+        paste_text("x", &options, None, &mut sink, &mut MemoryClipboard::default()).unwrap();
+        let downs:Vec<u8> = vk_events(sink.inputs()).into_iter().filter(|(_, up)| !up).map(|(vk, _)| vk).collect();
+        assert_eq!(downs, vks);
+    }
+}
+
+struct FailingSink;
+
+impl InputSink for FailingSink {
+    fn send(&mut self, _inputs: &[Input]) -> winput_stuffer::Result<u32> {
+        Err(Error::BlockedByUipi)
+    }
+}
+
+#[test]
+fn clipboard_is_restored_when_sending_fails() {
+    let mut clipboard = MemoryClipboard::with_text("old");
+    let mut clock = FakeClock::new();
+    assert!(paste_text_with_clock("new", &PasteOptions::default(), None, &mut FailingSink, &mut clipboard, &mut clock).is_err());
+    assert_eq!(clipboard.text.as_deref(), Some("old"));
+    // Nothing was pasted, so there's nothing to wait for
+    assert!(clock.sleeps.is_empty());
+}
+
+#[test]
+fn typing_or_pasting_depends_on_thresholds() {
+    let us = KeyboardLayout::us();
+    let paste = PasteOptions{min_len: 20, min_coverage_percent: 50, ..no_delay()};
+    assert!(!paste.should_paste("", &us));
+    assert!(!paste.should_paste("short\ntext", &us));
+    assert!(paste.should_paste("quite a bit longer text", &us));
+    // Mostly characters without a key
+    assert!(paste.should_paste("日本語 o", &us));
+    assert!(!paste.should_paste("ok 日本", &us));
+
+    let options = TextOptions{paste: Some(paste), ..Default::default()};
+    let mut sink = RecordingSink::new();
+    let mut clipboard = MemoryClipboard::default();
+    send_text_with_clipboard("hi", &us, &KeyState::default(), &options, &mut sink, &mut clipboard).unwrap();
+    assert_eq!(sink.inputs().len(), 4);
+    assert!(clipboard.history.is_empty());
+
+    sink.clear();
+    send_text_with_clipboard("日本語", &us, &KeyState::default(), &options, &mut sink, &mut clipboard).unwrap();
+    assert_eq!(vk_events(sink.inputs()).len(), 4);
+    assert_eq!(clipboard.history[0].as_deref(), Some("日本語"));
+
+    // Without paste options nothing is ever pasted
+    sink.clear();
+    let mut clipboard = MemoryClipboard::default();
+    send_text_with_clipboard("日本語", &us, &KeyState::default(), &TextOptions::default(), &mut sink, &mut clipboard).unwrap();
+    assert!(clipboard.history.is_empty());
+}
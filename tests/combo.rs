@@ -0,0 +1,188 @@
+use winput_stuffer::{Error, KeyboardLayout};
+use winput_stuffer::combo::{key_vk, parse_plover_combo, plover_combo_inputs, Combo};
+use winput_stuffer::input::Input;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::send::{key_event, send_combo_layout, send_plover_combo_layout};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::sink::RecordingSink;
+
+fn key(vk: u8, down: bool) -> Input {
+    Input::from_keyboard(&key_event(vk, down, None))
+}
+
+fn parse(combo: &str) -> Vec<u8> {
+    Combo::parse(combo, &KeyboardLayout::us()).unwrap().vks
+}
+
+#[test]
+fn modifiers_go_down_first_and_up_last() {
+    let us = KeyboardLayout::us();
+    let combo = Combo::parse("ctrl+shift+t", &us).unwrap();
+    assert_eq!(combo.inputs(None), vec![
+        key(VK_LCONTROL, true), key(VK_LSHIFT, true), key(VK_T, true),
+        key(VK_T, false), key(VK_LSHIFT, false), key(VK_LCONTROL, false),
+    ]);
+    assert_eq!(combo.to_string(), "LCONTROL+LSHIFT+T");
+
+    // Wherever they're written
+    assert_eq!(parse("t+shift+ctrl"), vec![VK_LSHIFT, VK_LCONTROL, VK_T]);
+    assert_eq!(parse("alt_l+tab"), vec![VK_LMENU, VK_TAB]);
+    assert_eq!(parse("Return"), vec![VK_RETURN]);
+}
+
+#[test]
+fn aliases_and_characters() {
+    assert_eq!(parse("control+C"), vec![VK_LCONTROL, VK_C]);
+    assert_eq!(parse("Ctrl + Alt + Delete"), vec![VK_LCONTROL, VK_LMENU, VK_DELETE]);
+    assert_eq!(parse("super+e"), parse("win+e"));
+    assert_eq!(parse("win_r+1"), vec![VK_RWIN, VK_DIGIT1]);
+    assert_eq!(parse("shift_r+ctrl_r+f5"), vec![VK_RSHIFT, VK_RCONTROL, VK_F5]);
+    assert_eq!(parse("ctrl+/"), vec![VK_LCONTROL, VK_OEM_2]);
+    // The unshifted plus, which is on the keypad
+    assert_eq!(parse("ctrl++"), vec![VK_LCONTROL, VK_ADD]);
+    assert_eq!(parse("+"), vec![VK_ADD]);
+}
+
+#[test]
+fn bad_combos_are_errors() {
+    let us = KeyboardLayout::us();
+    match Combo::parse("ctrl+shfit+t", &us) {
+        Err(Error::UnknownKeyName(name)) => assert_eq!(name, "shfit"),
+        other => panic!("expected an unknown key name, got {:?}", other),
+    }
+    for combo in ["", "ctrl+", "ctrl++t", "ctrl+ +t", "ctrl+t+ctrl", "control+ctrl+t"] {
+        assert!(matches!(Combo::parse(combo, &us), Err(Error::InvalidCombo(_))), "{:?}", combo);
+    }
+    assert!(matches!(key_vk("ab", &us), Err(Error::UnknownKeyName(_))));
+    assert!(matches!(key_vk("日", &us), Err(Error::UnknownKeyName(_))));
+
+    let mut sink = RecordingSink::new();
+    assert!(send_combo_layout("ctrl+nope", &us, &mut sink).is_err());
+    assert!(sink.inputs().is_empty());
+}
+
+#[test]
+fn combos_leave_nothing_held() {
+    let us = KeyboardLayout::us();
+    let mut sink = RecordingSink::new();
+    send_combo_layout("shift+a", &us, &mut sink).unwrap();
+    send_combo_layout("alt+tab", &us, &mut sink).unwrap();
+    send_combo_layout("tab", &us, &mut sink).unwrap();
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(sink.inputs());
+    assert_eq!(sim.text(), "A\t");
+    for vk in [VK_LSHIFT, VK_A, VK_LMENU, VK_TAB] {
+        assert!(!sim.is_down(vk));
+    }
+}
+
+// The Plover syntax tests are ported from Plover's test/test_key_combo.py
+
+/// Parses with key names as their own key codes, written the way Plover's tests write the result: "+a -a" for a tap of a.
+fn plover(combo: &str) -> Result<String, Error> {
+    let events = parse_plover_combo(combo, |name| Some(name.to_string()))?;
+    Ok(events.iter().map(|(key, down)| format!("{}{}", if *down { '+' } else { '-' }, key)).collect::<Vec<_>>().join(" "))
+}
+
+#[test]
+fn plover_noop() {
+    for combo in ["", "   "] {
+        assert_eq!(plover(combo).unwrap(), "");
+    }
+}
+
+#[test]
+fn plover_syntax_errors() {
+    for combo in [
+        // Invalid character
+        "Return,",
+        "Return&",
+        "Ret. urn <",
+        "exclam ! foo",
+        "shift[a]",
+        // Unbalanced )
+        ") arg",
+        "arg )",
+        "arg())",
+        "arg(x) )",
+        // Unbalanced (
+        "test(",
+        "foo(bar(",
+        "foo(bar(baz)",
+        // Misplaced (
+        "(",
+        "foo ( bar",
+        "(a)",
+    ] {
+        assert!(matches!(plover(combo), Err(Error::InvalidCombo(_))), "{:?}", combo);
+    }
+}
+
+#[test]
+fn plover_already_pressed() {
+    for combo in [
+        "foo(foo)",
+        "Foo(foO)",
+        "foo(fOo(arg) foo)",
+        "bar(foo(arg FOO))",
+    ] {
+        match plover(combo) {
+            Err(Error::InvalidCombo(msg)) => assert!(msg.contains("already pressed"), "{}", msg),
+            other => panic!("expected an error for {:?}, got {:?}", combo, other),
+        }
+    }
+}
+
+#[test]
+fn plover_stacking() {
+    for (combos, expected) in [
+        // 1 is not a valid identifier, but still a valid key name
+        (&["1"][..], "+1 -1"),
+        (&["Shift_l", "SHIFT_L"], "+shift_l -shift_l"),
+        // Case does not matter
+        (&["a", " A "], "+a -a"),
+        (&["a(b c)", "a ( b c   )"], "+a +b -b +c -c -a"),
+        (&["a(bc)", " a(  Bc )"], "+a +bc -bc -a"),
+        (&["a(bc(d)e  f(g) h())i j"], "+a +bc +d -d -bc +e -e +f +g -g -f +h -h -a +i -i +j -j"),
+        (&["foo () bar ( foo a b c (d))", "fOo () Bar ( FOO a B c (D))"], "+foo -foo +bar +foo -foo +a -a +b -b +c +d -d -c -bar"),
+    ] {
+        for combo in combos {
+            assert_eq!(plover(combo).unwrap(), expected, "{:?}", combo);
+        }
+    }
+}
+
+#[test]
+fn plover_error_messages() {
+    let message = |combo| match plover(combo) {
+        Err(Error::InvalidCombo(msg)) => msg,
+        other => panic!("expected an error, got {:?}", other),
+    };
+    assert_eq!(message("Return,"), "invalid character \",\" in \"Return[,]\"");
+    assert_eq!(message("arg )"), "unbalanced \")\" in \"arg [)]\"");
+    assert_eq!(message("foo(bar("), "unbalanced \"(\" in \"foo(bar([]\"");
+    assert_eq!(message("a(b A (c))"), "key \"a\" already pressed in \"a(b [A (]c))\"");
+}
+
+#[test]
+fn plover_combos_with_a_layout() {
+    let us = KeyboardLayout::us();
+    let inputs = plover_combo_inputs("alt_l(tab tab) control_l(c)", &us, None).unwrap();
+    assert_eq!(inputs, vec![
+        key(VK_LMENU, true), key(VK_TAB, true), key(VK_TAB, false), key(VK_TAB, true), key(VK_TAB, false), key(VK_LMENU, false),
+        key(VK_LCONTROL, true), key(VK_C, true), key(VK_C, false), key(VK_LCONTROL, false),
+    ]);
+    // Key names from Plover's vocabulary, which only picks the key, not the shift state
+    assert_eq!(plover_combo_inputs("exclam", &us, None).unwrap(), vec![key(VK_DIGIT1, true), key(VK_DIGIT1, false)]);
+    match plover_combo_inputs("shift(a Nope)", &us, None) {
+        Err(Error::InvalidCombo(message)) => assert_eq!(message, "unknown key in \"shift(a [Nope])\""),
+        other => panic!("expected an unknown key name, got {:?}", other),
+    }
+
+    let mut sink = RecordingSink::new();
+    send_plover_combo_layout("Shift_L(a b) Return", &us, &mut sink).unwrap();
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(sink.inputs());
+    assert_eq!(sim.text(), "AB\r");
+    assert!(!sim.is_down(VK_LSHIFT));
+}
@@ -0,0 +1,160 @@
+use winput_stuffer::input::*;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::layout::*;
+use winput_stuffer::send::inputs_for_text;
+
+fn vk_events(inputs: &[Input]) -> Vec<(u8, bool)> {
+    inputs.iter().map(|i| match i.as_keyboard().unwrap().e {
+        KeyboardInputEnum::VirtualKeyCode{code, ..} => (code.0 as u8, i.as_keyboard().unwrap().key_up),
+        other => panic!("expected a virtual key, got {:?}", other),
+    }).collect()
+}
+
+/// A few keys from the german layout: y and z swapped, a dead circumflex, and @ on AltGr+Q
+fn tiny_german() -> LayoutDescription {
+    LayoutDescription::new(HKL(0x04070407))
+        .key(KeyDescription::new(0x10, VK_Q).text(0, "q").text(SHIFT_STATE_SHIFT, "Q").text(SHIFT_STATE_CTRL | SHIFT_STATE_MENU, "@"))
+        .key(KeyDescription::new(0x15, VK_Z).text(0, "z").text(SHIFT_STATE_SHIFT, "Z"))
+        .key(KeyDescription::new(0x2C, VK_Y).text(0, "y").text(SHIFT_STATE_SHIFT, "Y"))
+        .key(KeyDescription::new(0x29, VK_OEM_5).dead(0, '^').text(SHIFT_STATE_SHIFT, "\u{b0}"))
+        .key(KeyDescription::new(0x1C, VK_RETURN).text(0, "\r"))
+        .key(KeyDescription::new(0x39, VK_SPACE).text(0, " "))
+}
+
+#[test]
+fn description_builds_lookup_tables() {
+    let layout = KeyboardLayout::from_description(&tiny_german());
+
+    assert_eq!(layout.id(), HKL(0x04070407));
+    assert_eq!(layout.char_to_vk_ss()[&'z'], (VK_Z, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'Y'], (VK_Y, SHIFT_STATE_SHIFT));
+    assert_eq!(layout.char_to_vk_ss()[&'@'], (VK_Q, SHIFT_STATE_CTRL | SHIFT_STATE_MENU));
+    assert_eq!(layout.char_to_vk_ss()[&'\u{b0}'], (VK_OEM_5, SHIFT_STATE_SHIFT));
+    // dead keys can't type their character directly, but are reachable by name
+    assert!(!layout.char_to_vk_ss().contains_key(&'^'));
+    assert_eq!(layout.keyname_to_vk()["dead_circumflex"], VK_OEM_5);
+    assert_eq!(layout.keyname_to_vk()["degree"], VK_OEM_5);
+    assert_eq!(layout.keyname_to_vk()["f1"], VK_F1);
+}
+
+#[test]
+fn planning_uses_the_described_layout() {
+    let layout = KeyboardLayout::from_description(&tiny_german());
+    let mut inputs = vec![];
+    inputs_for_text("zY", &layout, &mut inputs);
+
+    assert_eq!(vk_events(&inputs), vec![
+        (VK_Z, false),
+        (VK_Z, true),
+        (VK_SHIFT, false),
+        (VK_Y, false),
+        (VK_Y, true),
+        (VK_SHIFT, true),
+    ]);
+}
+
+#[test]
+fn us_layout() {
+    let layout = KeyboardLayout::us();
+    assert_eq!(layout.char_to_vk_ss()[&'a'], (VK_A, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'~'], (VK_OEM_3, SHIFT_STATE_SHIFT));
+    assert_eq!(layout.char_to_vk_ss()[&'\r'], (VK_RETURN, 0));
+    // Newlines are up to the planner
+    assert!(!layout.char_to_vk_ss().contains_key(&'\n'));
+    assert_eq!(layout.keyname_to_vk()["asciitilde"], VK_OEM_3);
+}
+
+#[test]
+fn bundled_layouts_by_klid() {
+    for bundled in BUNDLED_LAYOUTS {
+        let layout = KeyboardLayout::bundled(bundled.klid).unwrap();
+        assert_eq!(layout.id(), bundled.id);
+        assert_eq!(BundledLayout::by_id(bundled.id).unwrap().klid, bundled.klid);
+        for c in "0123456789 \t\r".chars() {
+            assert!(layout.char_to_vk_ss().contains_key(&c), "{} can't type {:?}", bundled.name, c);
+        }
+    }
+    assert_eq!(KeyboardLayout::bundled("0000040c").unwrap().id(), HKL(0x040C040C));
+    assert!(KeyboardLayout::bundled("00000411").is_none());
+    assert_eq!(KeyboardLayout::current_or_bundled("00000411").id(), KeyboardLayout::us().id());
+}
+
+#[test]
+fn german_layout() {
+    let layout = KeyboardLayout::bundled("00000407").unwrap();
+    assert_eq!(layout.char_to_vk_ss()[&'z'], (VK_Z, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'ß'], (VK_OEM_4, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'Ä'], (VK_OEM_7, SHIFT_STATE_SHIFT));
+    assert_eq!(layout.char_to_vk_ss()[&'€'], (VK_E, SHIFT_STATE_CTRL | SHIFT_STATE_MENU));
+    assert_eq!(layout.keyname_to_vk()["dead_circumflex"], VK_OEM_5);
+    assert_eq!(layout.keyname_to_vk()["dead_acute"], VK_OEM_6);
+    assert!(!layout.char_to_vk_ss().contains_key(&'^'));
+
+    let mut inputs = vec![];
+    inputs_for_text("y", &layout, &mut inputs);
+    assert_eq!(vk_events(&inputs), vec![(VK_Y, false), (VK_Y, true)]);
+}
+
+#[test]
+fn french_layout() {
+    let layout = KeyboardLayout::bundled("0000040C").unwrap();
+    assert_eq!(layout.char_to_vk_ss()[&'1'], (VK_DIGIT1, SHIFT_STATE_SHIFT));
+    assert_eq!(layout.char_to_vk_ss()[&'&'], (VK_DIGIT1, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'q'], (VK_Q, 0));
+    assert_eq!(layout.char_to_vk_ss()[&'@'], (VK_DIGIT0, SHIFT_STATE_CTRL | SHIFT_STATE_MENU));
+    assert_eq!(layout.keyname_to_vk()["dead_diaeresis"], VK_OEM_6);
+    assert_eq!(layout.keyname_to_vk()["dead_tilde"], VK_DIGIT2);
+}
+
+#[test]
+fn dvorak_and_russian_layouts() {
+    let dvorak = KeyboardLayout::bundled("00010409").unwrap();
+    assert_eq!(dvorak.char_to_vk_ss()[&'s'], (VK_S, 0));
+    assert_eq!(dvorak.char_to_vk_ss()[&'-'], (VK_OEM_MINUS, 0));
+
+    let russian = KeyboardLayout::bundled("00000419").unwrap();
+    assert_eq!(russian.char_to_vk_ss()[&'я'], (VK_Z, 0));
+    assert_eq!(russian.char_to_vk_ss()[&'Ё'], (VK_OEM_3, SHIFT_STATE_SHIFT));
+    assert_eq!(russian.char_to_vk_ss()[&'№'], (VK_DIGIT3, SHIFT_STATE_SHIFT));
+    assert!(!russian.char_to_vk_ss().contains_key(&'q'));
+}
+
+#[test]
+fn altgr_is_planned_as_right_alt() {
+    let german = KeyboardLayout::bundled("00000407").unwrap();
+    assert!(german.has_altgr());
+    assert_eq!(german.ss_to_vks()[&SHIFT_STATE_ALTGR], vec![VK_LCONTROL, VK_RMENU]);
+
+    let mut inputs = vec![];
+    inputs_for_text("@", &german, &mut inputs);
+    assert_eq!(vk_events(&inputs), vec![
+        (VK_LCONTROL, false),
+        (VK_RMENU, false),
+        (VK_Q, false),
+        (VK_Q, true),
+        (VK_RMENU, true),
+        (VK_LCONTROL, true),
+    ]);
+    assert!(inputs.iter().any(|i| matches!(i.as_keyboard().unwrap().e, KeyboardInputEnum::VirtualKeyCode{code, extended: true} if code.0 == VK_RMENU as u16)));
+
+    assert!(!KeyboardLayout::us().has_altgr());
+    assert!(!KeyboardLayout::bundled("00000419").unwrap().has_altgr());
+}
+
+#[test]
+fn ctrl_is_never_used_to_type_characters() {
+    // Windows reports Ctrl+C as typing "\x03", but pressing it would copy
+    let layout = KeyboardLayout::from_description(&LayoutDescription::new(HKL(0x04090409))
+        .key(KeyDescription::new(0x2E, VK_C).text(0, "c").text(SHIFT_STATE_CTRL, "\x03"))
+        .key(KeyDescription::new(0x1C, VK_RETURN).text(0, "\r")));
+    assert!(!layout.has_altgr());
+    assert_eq!(layout.vk_ss_to_char()[&(VK_C, SHIFT_STATE_CTRL)], '\x03');
+    assert!(!layout.char_to_vk_ss().contains_key(&'\x03'));
+
+    // Typing something with Ctrl+Alt is what gives AltGr away
+    let layout = KeyboardLayout::from_description(&LayoutDescription::new(HKL(0x04090409))
+        .key(KeyDescription::new(0x12, VK_E).text(0, "e").text(SHIFT_STATE_ALTGR, "€"))
+        .key(KeyDescription::new(0x1C, VK_RETURN).text(0, "\r")));
+    assert!(layout.has_altgr());
+    assert_eq!(layout.char_to_vk_ss()[&'€'], (VK_E, SHIFT_STATE_ALTGR));
+}
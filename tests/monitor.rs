@@ -0,0 +1,101 @@
+use winput_stuffer::Error;
+use winput_stuffer::input::{Input, MouseInput, MouseInputEnum, MouseMovement};
+use winput_stuffer::monitor::{Monitor, Monitors, Rect};
+use winput_stuffer::send::{move_to_monitor_relative_with_monitors, move_to_pixel_with_monitors};
+use winput_stuffer::sink::RecordingSink;
+
+/// A 1280x1024 monitor left of a 1920x1080 primary one, and a 2560x1440 one above the primary one at 150%:
+/// ```text
+///            +---------+
+///            |    2    |
+///            |         |
+/// +------+---+---+-----+
+/// |  1   |   0   |
+/// +------+-------+
+/// ```
+fn three_monitors() -> Monitors {
+    Monitors::new(vec![
+        Monitor::new(Rect::new(-1280, 0, 1280, 1024), false),
+        Monitor::new(Rect::new(500, -1440, 2560, 1440), false).with_scale(1.5),
+        Monitor::new(Rect::new(0, 0, 1920, 1080), true),
+    ])
+}
+
+/// Where Windows would put the mouse for a movement
+fn landing(monitors: &Monitors, m: MouseMovement) -> (i32, i32) {
+    match m {
+        MouseMovement::AbsoluteVirtualDesktop{x, y} => monitors.virtual_desktop().normalized_to_pixel(x, y),
+        other => panic!("expected a virtual desktop movement, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_pixel_round_trips() {
+    for width in [1, 2, 3, 640, 1366, 1920, 2560, 3840, 7680, 65535, 65536] {
+        let rect = Rect::new(-(width as i32) / 2, 0, width, 1);
+        for x in rect.x..rect.x + width as i32 {
+            let (nx, _) = rect.pixel_to_normalized(x, 0).unwrap();
+            assert_eq!(rect.normalized_to_pixel(nx, 0).0, x, "{} wide", width);
+        }
+        assert_eq!(rect.pixel_to_normalized(rect.x - 1, 0), None);
+        assert_eq!(rect.pixel_to_normalized(rect.x + width as i32, 0), None);
+    }
+    // The corners are the ends of the normalized range
+    let rect = Rect::new(0, 0, 1920, 1080);
+    assert_eq!(rect.pixel_to_normalized(0, 0), Some((0, 0)));
+    assert_eq!(rect.normalized_to_pixel(65535, 65535), (1919, 1079));
+}
+
+#[test]
+fn geometry() {
+    let monitors = three_monitors();
+    assert_eq!(monitors.monitors.iter().map(|m| m.rect.x).collect::<Vec<_>>(), [0, -1280, 500]);
+    assert_eq!(monitors.primary().unwrap().rect, Rect::new(0, 0, 1920, 1080));
+    assert_eq!(monitors.virtual_desktop(), Rect::new(-1280, -1440, 4340, 2520));
+    assert_eq!(monitors.monitor_at(-1, 1023).unwrap().rect.x, -1280);
+    assert_eq!(monitors.monitor_at(3059, -1).unwrap().scale, 1.5);
+    assert_eq!(monitors.monitor_at(-1, 1024), None);
+    assert_eq!(monitors.monitor_at(0, -1), None);
+    assert_eq!(Monitors::default().virtual_desktop(), Rect::default());
+}
+
+#[test]
+fn moving_to_pixels() {
+    let monitors = three_monitors();
+    assert_eq!(monitors.move_to_pixel(-1280, 0).unwrap(), MouseMovement::AbsoluteVirtualDesktop{x: 0, y: 37450});
+    for (x, y) in [(0, 0), (1919, 1079), (-1280, 1023), (-1, 500), (500, -1440), (3059, -1), (1000, -720)] {
+        assert_eq!(landing(&monitors, monitors.move_to_pixel(x, y).unwrap()), (x, y));
+    }
+    // The top-left of the virtual desktop is in the gap between monitors 1 and 2
+    assert!(matches!(monitors.move_to_pixel(-1280, -1440), Err(Error::OffScreen{x: -1280, y: -1440})));
+    assert!(matches!(monitors.move_to_pixel(1920, 0), Err(Error::OffScreen{..})));
+}
+
+#[test]
+fn moving_relative_to_monitors() {
+    let monitors = three_monitors();
+    assert_eq!(landing(&monitors, monitors.move_to_monitor_relative(0, 10, 20).unwrap()), (10, 20));
+    assert_eq!(landing(&monitors, monitors.move_to_monitor_relative(1, 0, 0).unwrap()), (-1280, 0));
+    assert_eq!(landing(&monitors, monitors.move_to_monitor_relative(1, 1279, 1023).unwrap()), (-1, 1023));
+    assert_eq!(landing(&monitors, monitors.move_to_monitor_relative(2, 2559, 0).unwrap()), (3059, -1440));
+    // Pixels past the edge of the monitor are fine as long as another monitor shows them
+    assert_eq!(landing(&monitors, monitors.move_to_monitor_relative(1, 1280, 0).unwrap()), (0, 0));
+    assert!(matches!(monitors.move_to_monitor_relative(1, 0, 1024), Err(Error::OffScreen{x: -1280, y: 1024})));
+    assert!(matches!(monitors.move_to_monitor_relative(3, 0, 0), Err(Error::NoSuchMonitor(3))));
+}
+
+#[test]
+fn sending() {
+    let monitors = three_monitors();
+    let mut sink = RecordingSink::new();
+    move_to_pixel_with_monitors(&monitors, -1280, 0, &mut sink).unwrap();
+    move_to_monitor_relative_with_monitors(&monitors, 1, 0, 0, &mut sink).unwrap();
+    let expected = Input::from_mouse(&MouseInput{
+        e: MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x: 0, y: 37450}, coalesce: false},
+        msg: None,
+        time: None,
+    });
+    assert_eq!(sink.inputs(), [expected, expected]);
+    assert!(move_to_pixel_with_monitors(&monitors, 5000, 0, &mut sink).is_err());
+    assert_eq!(sink.inputs().len(), 2);
+}
@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use winput_stuffer::KeyboardLayout;
+use winput_stuffer::input::Input;
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::pace::{chunks, send_paced, strokes, Clock, FakeClock, Pacing};
+use winput_stuffer::send::{inputs_for_text, send_text_paced, TextOptions};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::sink::{InputSink, RecordingSink};
+
+fn planned(text: &str) -> Vec<Input> {
+    let mut inputs = vec![];
+    inputs_for_text(text, &KeyboardLayout::us(), &mut inputs);
+    inputs
+}
+
+#[test]
+fn strokes_keep_keys_and_surrogates_together() {
+    let us = KeyboardLayout::us();
+    // Shift+H, i, the emoji as a surrogate pair, !
+    let inputs = planned("Hi😀!");
+    let parts = strokes(&inputs);
+    assert_eq!(parts.iter().map(|s| s.len()).collect::<Vec<_>>(), vec![4, 2, 4, 4]);
+    for (stroke, text) in parts.iter().zip(["H", "i", "😀", "!"]) {
+        let mut sim = KeyboardSimulator::new(&us);
+        sim.feed_all(*stroke);
+        assert_eq!(sim.text(), text);
+    }
+
+    // Keys left down make up one last stroke
+    assert_eq!(strokes(&inputs[..6]).len(), 2);
+    assert_eq!(strokes(&inputs[..7]).len(), 3);
+    assert!(strokes(&[]).is_empty());
+}
+
+#[test]
+fn chunks_group_strokes() {
+    let inputs = planned("Hello, world");
+    let chunks = chunks(&inputs, 5);
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks.concat(), inputs);
+    assert_eq!(strokes(chunks[0]).len(), 5);
+    assert_eq!(strokes(chunks[2]).len(), 2);
+}
+
+#[test]
+fn pacing() {
+    assert_eq!(Pacing::chars_per_second(10.0, 5).unwrap().chunk_interval(), Duration::from_millis(500));
+    // 60 wpm is 5 characters a second
+    assert_eq!(Pacing::words_per_minute(60.0, 1).unwrap().chunk_interval(), Duration::from_millis(200));
+    assert_eq!(Pacing::chars_per_second(0.0, 1).unwrap().chunk_interval(), Duration::ZERO);
+    // Too slow for the interval to fit in a Duration
+    assert_eq!(Pacing::chars_per_second(1e-300, 1).unwrap().chunk_interval(), Duration::MAX);
+    assert!(Pacing::chars_per_second(f64::NAN, 1).is_err());
+    assert!(Pacing::words_per_minute(f64::INFINITY, 1).is_err());
+}
+
+#[test]
+fn paced_sending_sleeps_between_chunks() {
+    let us = KeyboardLayout::us();
+    let mut sink = RecordingSink::new();
+    let mut clock = FakeClock::new();
+    let pacing = Pacing::chars_per_second(10.0, 2).unwrap();
+    send_text_paced("hello", &us, &KeyState::default(), &TextOptions::default(), &pacing, &mut sink, &mut clock).unwrap();
+    assert_eq!(sink.batch_lens(), [4, 4, 2]);
+    assert_eq!(clock.sleeps, vec![Duration::from_millis(200); 2]);
+
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(sink.inputs());
+    assert_eq!(sim.text(), "hello");
+}
+
+/// A `FakeClock` that a sink can move on too
+#[derive(Clone, Default)]
+struct SharedClock(Rc<RefCell<FakeClock>>);
+
+impl Clock for SharedClock {
+    fn now(&self) -> Duration {
+        self.0.borrow().now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.0.borrow_mut().sleep(duration)
+    }
+}
+
+/// Takes 150ms to send each batch
+struct SlowSink {
+    clock: SharedClock,
+    sink: RecordingSink,
+}
+
+impl InputSink for SlowSink {
+    fn send(&mut self, inputs: &[Input]) -> winput_stuffer::Result<u32> {
+        self.clock.0.borrow_mut().advance(Duration::from_millis(150));
+        self.sink.send(inputs)
+    }
+}
+
+#[test]
+fn sending_time_counts_towards_the_wait() {
+    let inputs = planned("abcd");
+    let mut clock = SharedClock::default();
+    let mut sink = SlowSink{clock: clock.clone(), sink: RecordingSink::new()};
+    send_paced(&inputs, &Pacing::chars_per_second(5.0, 1).unwrap(), 0, &mut sink, &mut clock).unwrap();
+    assert_eq!(clock.0.borrow().sleeps, vec![Duration::from_millis(50); 3]);
+    assert_eq!(sink.sink.batch_lens(), [2, 2, 2, 2]);
+
+    // Batches slower than the interval go one after another without waiting
+    let mut clock = SharedClock::default();
+    let mut sink = SlowSink{clock: clock.clone(), sink: RecordingSink::new()};
+    send_paced(&inputs, &Pacing::chars_per_second(10.0, 1).unwrap(), 0, &mut sink, &mut clock).unwrap();
+    assert!(clock.0.borrow().sleeps.is_empty());
+
+    let mut clock = SharedClock::default();
+    let mut sink = SlowSink{clock: clock.clone(), sink: RecordingSink::new()};
+    send_paced(&inputs, &Pacing::chars_per_second(5.0, 2).unwrap(), 0, &mut sink, &mut clock).unwrap();
+    assert_eq!(clock.0.borrow().sleeps, vec![Duration::from_millis(250)]);
+}
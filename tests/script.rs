@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use winput_stuffer::{Error, KeyboardLayout};
+use winput_stuffer::combo::Combo;
+use winput_stuffer::input::{Input, MouseButton, MouseInput, MouseInputEnum};
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::pace::FakeClock;
+use winput_stuffer::script::{Script, Statement, Step, Timeline};
+use winput_stuffer::send::{key_event, TextOptions};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::sink::{InputSink, RecordingSink};
+
+fn key(vk: u8, down: bool) -> Input {
+    Input::from_keyboard(&key_event(vk, down, None))
+}
+
+fn button(which: MouseButton, button_up: bool) -> Input {
+    Input::from_mouse(&MouseInput{e: MouseInputEnum::Button{which, button_up}, msg: None, time: None})
+}
+
+fn compile(source: &str) -> Timeline {
+    let us = KeyboardLayout::us();
+    Script::parse(source, &us).unwrap().compile(&us, &KeyState::default(), &TextOptions::default())
+}
+
+/// The (line, column, message) of a syntax error
+fn syntax_error(source: &str) -> (usize, usize, String) {
+    match Script::parse(source, &KeyboardLayout::us()) {
+        Err(Error::Syntax{line, column, message}) => (line, column, message),
+        other => panic!("expected a syntax error for {:?}, got {:?}", source, other),
+    }
+}
+
+#[test]
+fn parsing() {
+    let us = KeyboardLayout::us();
+    let script = Script::parse("
+        # Comments and blank lines are skipped
+
+        type \"a \\\"quote\\\"\\n\" # and comments after commands
+        KEY ctrl+s
+        down shift
+        up Shift_L
+        sleep 1.5s
+        repeat 3 {
+            repeat 2 {
+                click
+            }
+            sleep 10
+        }
+        move 100 -20
+        click right
+        scroll -3
+    ", &us).unwrap();
+    assert_eq!(script.statements, vec![
+        Statement::Type("a \"quote\"\n".into()),
+        Statement::Key(Combo::parse("ctrl+s", &us).unwrap()),
+        Statement::Down(VK_LSHIFT),
+        Statement::Up(VK_LSHIFT),
+        Statement::Sleep(Duration::from_millis(1500)),
+        Statement::Repeat(3, vec![
+            Statement::Repeat(2, vec![Statement::Click(MouseButton::Left)]),
+            Statement::Sleep(Duration::from_millis(10)),
+        ]),
+        Statement::Move{dx: 100, dy: -20},
+        Statement::Click(MouseButton::Right),
+        Statement::Scroll(-3),
+    ]);
+}
+
+#[test]
+fn compiling() {
+    let timeline = compile("
+        down alt_l
+        repeat 2 {
+            key tab
+            sleep 200ms
+        }
+        up alt_l
+    ");
+    assert_eq!(timeline.steps, vec![
+        Step::Inputs(vec![key(VK_LMENU, true), key(VK_TAB, true), key(VK_TAB, false)]),
+        Step::Sleep(Duration::from_millis(200)),
+        Step::Inputs(vec![key(VK_TAB, true), key(VK_TAB, false)]),
+        Step::Sleep(Duration::from_millis(200)),
+        Step::Inputs(vec![key(VK_LMENU, false)]),
+    ]);
+    assert_eq!(timeline.duration(), Duration::from_millis(400));
+
+    // Sleeps next to each other are merged, and so are batches
+    let timeline = compile("sleep 1s\nsleep 500ms\nclick\nclick middle\nrepeat 0 {\n}\n");
+    assert_eq!(timeline.steps, vec![
+        Step::Sleep(Duration::from_millis(1500)),
+        Step::Inputs(vec![
+            button(MouseButton::Left, false), button(MouseButton::Left, true),
+            button(MouseButton::Middle, false), button(MouseButton::Middle, true),
+        ]),
+    ]);
+
+    let us = KeyboardLayout::us();
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(compile("type \"Hi\"\nkey shift+1\ntype \"\\tthere\"").inputs());
+    assert_eq!(sim.text(), "Hi!\tthere");
+}
+
+#[test]
+fn dry_run() {
+    let timeline = compile("key ctrl+s\nsleep 200ms\nmove 5 -5\nscroll -1\ntype \"😀\"");
+    assert_eq!(timeline.to_string(), "\
+send LCONTROL down, S down, S up, LCONTROL up
+sleep 200ms
+send move by 5,-5, wheel -120, U+D83D down, U+DE00 down, U+D83D up, U+DE00 up
+");
+}
+
+#[test]
+fn running() {
+    let timeline = compile("key a\nsleep 200ms\nkey b\nsleep 1s");
+    let mut sink = RecordingSink::new();
+    let mut clock = FakeClock::new();
+    let report = timeline.run(0, &mut sink, &mut clock).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.total, 4);
+    assert_eq!(sink.batch_lens(), [2, 2]);
+    assert_eq!(clock.sleeps, vec![Duration::from_millis(200), Duration::from_secs(1)]);
+}
+
+/// Accepts `accept` inputs of the batch numbered `fail_on` (counting from 1) and fails if that's none of them, and accepts every other batch whole
+struct FailingSink {
+    batches: usize,
+    fail_on: usize,
+    accept: u32,
+    sent: Vec<Input>,
+}
+
+impl FailingSink {
+    fn new(fail_on: usize, accept: u32) -> Self {
+        Self{batches: 0, fail_on, accept, sent: vec![]}
+    }
+}
+
+impl InputSink for FailingSink {
+    fn send(&mut self, inputs: &[Input]) -> winput_stuffer::Result<u32> {
+        self.batches += 1;
+        let count = if self.batches == self.fail_on { self.accept } else { inputs.len() as u32 };
+        if count == 0 {
+            return Err(Error::BlockedByUipi);
+        }
+        self.sent.extend_from_slice(&inputs[..count as usize]);
+        Ok(count)
+    }
+}
+
+#[test]
+fn keys_held_by_earlier_batches_are_let_go_on_failure() {
+    let timeline = compile("down alt_l\nsleep 200ms\nkey tab\nup alt_l");
+
+    let mut sink = FailingSink::new(2, 0);
+    let report = match timeline.run(0, &mut sink, &mut FakeClock::new()) {
+        Err(Error::PartialSend(report)) => report,
+        other => panic!("expected a partial send, got {:?}", other),
+    };
+    assert_eq!((report.accepted, report.total), (1, 4));
+    assert_eq!(report.released, [key(VK_LMENU, false)]);
+    assert!(report.stuck.is_empty());
+    assert_eq!(sink.sent, [key(VK_LMENU, true), key(VK_LMENU, false)]);
+
+    // Only Tab going down gets through, and send_all lets go of it before the run lets go of Alt
+    let mut sink = FailingSink::new(2, 1);
+    let report = match timeline.run(0, &mut sink, &mut FakeClock::new()) {
+        Err(Error::PartialSend(report)) => report,
+        other => panic!("expected a partial send, got {:?}", other),
+    };
+    assert_eq!(report.accepted, 2);
+    assert_eq!(report.released, [key(VK_TAB, false), key(VK_LMENU, false)]);
+    assert_eq!(sink.sent, [key(VK_LMENU, true), key(VK_TAB, true), key(VK_TAB, false), key(VK_LMENU, false)]);
+}
+
+#[test]
+fn errors_have_lines_and_columns() {
+    for (source, expected) in [
+        ("jump 3", (1, 1, "unknown command \"jump\"")),
+        ("key a\n  key ctrl+nope", (2, 7, "Unknown key name \"nope\"")),
+        ("down", (1, 5, "expected a key name")),
+        ("type hello", (1, 6, "expected a quoted string")),
+        ("type \"hello", (1, 6, "unterminated string")),
+        ("type \"a\\qb\"", (1, 8, "unknown escape, expected \\n, \\t, \\r, \\\" or \\\\")),
+        ("sleep soon", (1, 7, "expected a duration like 200ms")),
+        ("move 1", (1, 7, "expected a distance in pixels")),
+        ("click left twice", (1, 12, "unexpected extra argument")),
+        ("click up", (1, 7, "expected left, right, middle, x1 or x2")),
+        ("scroll 1.5", (1, 8, "expected a number of wheel clicks")),
+        ("repeat 2\nkey a\n}", (1, 9, "expected \"{\"")),
+        ("key a\n}", (2, 1, "\"}\" without a repeat to close")),
+        ("repeat 2 {\n  repeat 3 {\n  }\n", (1, 1, "repeat without a closing \"}\"")),
+        ("é { x", (1, 1, "unknown command \"é\"")),
+        ("\"quoted\"", (1, 1, "expected a command")),
+        ("repeat 4294967295 {\n  key a\n}", (1, 1, "repeat makes the script too long to compile")),
+        ("repeat 1000 {\n  repeat 1000 {\n    key a\n  }\n  key b\n}", (1, 1, "repeat makes the script too long to compile")),
+        ("key a\n  repeat 100000 {\n    type \"twenty characters...\"\n  }", (2, 3, "repeat makes the script too long to compile")),
+        ("repeat 4294967295 {\n  repeat 4294967295 {\n  }\n}", (2, 3, "repeat makes the script too long to compile")),
+    ] {
+        let (line, column, message) = syntax_error(source);
+        assert_eq!((line, column, message.as_str()), expected, "{:?}", source);
+    }
+    // Columns count characters, not bytes
+    assert_eq!(syntax_error("key a\n\tkey é é").1, 8);
+    assert_eq!(Error::syntax_at("ab\ncdé\nf", 7, "here").to_string(), "2:4: here");
+}
@@ -0,0 +1,331 @@
+use quickcheck::{quickcheck, TestResult};
+
+use winput_stuffer::input::*;
+use winput_stuffer::layout::*;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::send::{inputs_for_key_with_state, inputs_for_text, inputs_for_text_with_state, key_event, send_text_with_msg_layout};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::KeyboardLayout;
+
+fn typed(layout: &KeyboardLayout, inputs: &[Input]) -> String {
+    let mut sim = KeyboardSimulator::new(layout);
+    sim.feed_all(inputs);
+    sim.take_text()
+}
+
+fn keys(events: &[(u8, bool)]) -> Vec<Input> {
+    events.iter().map(|(vk, down)| Input::from_keyboard(&key_event(*vk, *down, None))).collect()
+}
+
+/// Enter types "\r", so that's what a newline comes back as
+fn roundtrip(layout: &KeyboardLayout, text: &str) -> String {
+    let mut inputs = vec![];
+    inputs_for_text(text, layout, &mut inputs);
+    typed(layout, &inputs).replace('\r', "\n")
+}
+
+#[test]
+fn text_roundtrips_on_every_bundled_layout() {
+    let text = "Hello, World! 123 ~`@#$%^&*()_+-=[]{};':\",./<>?\\| äöüß ÀÉ € £ ё Жж 😀\t\n";
+    for bundled in BUNDLED_LAYOUTS {
+        assert_eq!(roundtrip(&bundled.layout(), text), text, "on {}", bundled.name);
+    }
+}
+
+#[test]
+fn modifiers_are_tracked() {
+    let us = KeyboardLayout::us();
+    assert_eq!(typed(&us, &keys(&[
+        (VK_A, true), (VK_A, false),
+        (VK_LSHIFT, true), (VK_A, true), (VK_A, false), (VK_DIGIT1, true), (VK_DIGIT1, false), (VK_LSHIFT, false),
+        (VK_A, true), (VK_A, false),
+        // Nothing in the US layout is typed with Ctrl
+        (VK_CONTROL, true), (VK_A, true), (VK_A, false), (VK_CONTROL, false),
+    ])), "aA!a");
+}
+
+#[test]
+fn altgr_is_ctrl_and_alt() {
+    let german = KeyboardLayout::bundled("00000407").unwrap();
+    assert_eq!(typed(&german, &keys(&[
+        (VK_RMENU, true), (VK_LCONTROL, true), (VK_Q, true), (VK_Q, false), (VK_LCONTROL, false), (VK_RMENU, false),
+        (VK_Q, true), (VK_Q, false),
+        // AltGr by itself is enough, windows adds the left Control
+        (VK_RMENU, true), (VK_E, true), (VK_E, false), (VK_RMENU, false),
+    ])), "@q€");
+    // But on a layout without AltGr right Alt is just Alt
+    assert_eq!(typed(&KeyboardLayout::us(), &keys(&[(VK_RMENU, true), (VK_E, true), (VK_E, false), (VK_RMENU, false)])), "");
+}
+
+#[test]
+fn dead_keys() {
+    let german = KeyboardLayout::bundled("00000407").unwrap();
+    let mut sim = KeyboardSimulator::new(&german);
+
+    sim.feed_all(&keys(&[(VK_OEM_5, true), (VK_OEM_5, false)]));
+    assert_eq!(sim.pending_dead_char(), Some('^'));
+    assert_eq!(sim.text(), "");
+    sim.feed_all(&keys(&[(VK_SPACE, true), (VK_SPACE, false)]));
+    assert_eq!(sim.take_text(), "^");
+
+    // Without a composition, the dead key comes out followed by the character
+    sim.feed_all(&keys(&[(VK_OEM_5, true), (VK_OEM_5, false), (VK_X, true), (VK_X, false)]));
+    assert_eq!(sim.take_text(), "^x");
+
+    sim.feed_all(&keys(&[(VK_OEM_5, true), (VK_OEM_5, false), (VK_OEM_5, true), (VK_OEM_5, false)]));
+    assert_eq!(sim.take_text(), "^^");
+    assert_eq!(sim.pending_dead_char(), None);
+}
+
+#[test]
+fn surrogate_pairs() {
+    let us = KeyboardLayout::us();
+    let unicode = |units: &[u16]| -> Vec<Input> {
+        units.iter().flat_map(|u| [false, true].map(|key_up| Input::from_keyboard(&KeyboardInput{
+            e: KeyboardInputEnum::UnicodeCodeUnit(*u),
+            key_up,
+            msg: None,
+            time: None,
+        }))).collect()
+    };
+    assert_eq!(typed(&us, &unicode(&[0xD83D, 0xDE00, 0x263A])), "😀☺");
+    // A high surrogate that isn't followed by a low one can't be decoded
+    assert_eq!(typed(&us, &unicode(&[0xD83D, 0x263A])), "\u{FFFD}☺");
+}
+
+#[test]
+fn simulator_is_a_sink() {
+    let french = KeyboardLayout::bundled("0000040C").unwrap();
+    let mut sim = KeyboardSimulator::new(&french);
+    send_text_with_msg_layout("azerty 1&", None, &french, &mut sim).unwrap();
+    assert_eq!(sim.text(), "azerty 1&");
+    assert!(!sim.is_down(VK_SHIFT));
+}
+
+quickcheck! {
+    fn any_text_roundtrips(text: String, layout_index: usize, caps_lock: bool) -> TestResult {
+        // "\r\n" collapses into one Enter, and there's no way to type most control characters
+        if text.chars().any(|c| c.is_control() && c != '\t' && c != '\n') {
+            return TestResult::discard();
+        }
+        let layout = BUNDLED_LAYOUTS[layout_index % BUNDLED_LAYOUTS.len()].layout();
+        let mut sim = KeyboardSimulator::new(&layout);
+        sim.set_toggled(VK_CAPITAL, caps_lock);
+        let mut inputs = vec![];
+        inputs_for_text_with_state(&text, &layout, &sim, &mut inputs);
+        sim.feed_all(&inputs);
+        TestResult::from_bool(sim.text().replace('\r', "\n") == text && sim.is_toggled(VK_CAPITAL) == caps_lock)
+    }
+}
+
+#[test]
+fn caps_lock_flips_shift_for_letters() {
+    let us = KeyboardLayout::us();
+    let caps = KeyState::default().with_toggled(VK_CAPITAL);
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.set_toggled(VK_CAPITAL, true);
+
+    // What used to happen
+    let mut inputs = vec![];
+    inputs_for_text("Hello!", &us, &mut inputs);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.take_text(), "hELLO!");
+
+    inputs.clear();
+    inputs_for_text_with_state("Hello!", &us, &caps, &mut inputs);
+    assert_eq!(inputs.iter().filter(|i| matches!(i.as_keyboard().unwrap().e, KeyboardInputEnum::VirtualKeyCode{code, ..} if code.0 == VK_CAPITAL as u16)).count(), 0);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.take_text(), "Hello!");
+
+    let german = KeyboardLayout::bundled("00000407").unwrap();
+    let mut sim = KeyboardSimulator::new(&german);
+    sim.set_toggled(VK_CAPITAL, true);
+    inputs.clear();
+    inputs_for_text_with_state("äÄéÉ", &german, &caps, &mut inputs);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.take_text(), "äÄéÉ");
+}
+
+#[test]
+fn caps_lock_is_turned_off_for_sgcaps_keys_that_need_it() {
+    // The Swiss German ü key: Shift types è, but Caps Lock makes it type Ü and È instead
+    let swiss = KeyboardLayout::from_description(&LayoutDescription::new(HKL(0x08070807))
+        .key(KeyDescription::new(0x1A, VK_OEM_1).text(0, "ü").text(SHIFT_STATE_SHIFT, "è").caps_text(0, "Ü").caps_text(SHIFT_STATE_SHIFT, "È"))
+        .key(KeyDescription::new(0x1E, VK_A).text(0, "a").text(SHIFT_STATE_SHIFT, "A").caps_lock())
+        .key(KeyDescription::new(0x1C, VK_RETURN).text(0, "\r")));
+    assert_eq!(swiss.vk_ss_for_char('Ü', true), Some((VK_OEM_1, 0)));
+    assert_eq!(swiss.vk_ss_for_char('è', true), None);
+
+    let mut sim = KeyboardSimulator::new(&swiss);
+    sim.set_toggled(VK_CAPITAL, true);
+    let mut inputs = vec![];
+    inputs_for_text_with_state("aè", &swiss, &sim, &mut inputs);
+    assert_eq!(vk_events(&inputs).first(), Some(&(VK_CAPITAL, false)));
+    assert_eq!(vk_events(&inputs).last(), Some(&(VK_CAPITAL, true)));
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), "aè");
+    assert!(sim.is_toggled(VK_CAPITAL));
+}
+
+#[test]
+fn num_lock_is_turned_on_for_keypad_digits() {
+    let us = KeyboardLayout::us();
+    let mut inputs = vec![];
+    inputs_for_key_with_state("kp_7", true, &us, &KeyState::default(), &mut inputs).unwrap();
+    inputs_for_key_with_state("kp_7", false, &us, &KeyState::default(), &mut inputs).unwrap();
+    assert_eq!(vk_events(&inputs), vec![
+        (VK_NUMLOCK, false), (VK_NUMLOCK, true),
+        (VK_NUMPAD7, false),
+        (VK_NUMLOCK, false), (VK_NUMLOCK, true),
+        (VK_NUMPAD7, true),
+    ]);
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), "7");
+    assert!(!sim.is_toggled(VK_NUMLOCK));
+
+    inputs.clear();
+    inputs_for_key_with_state("kp_7", true, &us, &KeyState::default().with_toggled(VK_NUMLOCK), &mut inputs).unwrap();
+    inputs_for_key_with_state("home", true, &us, &KeyState::default(), &mut inputs).unwrap();
+    assert_eq!(vk_events(&inputs), vec![(VK_NUMPAD7, false), (VK_HOME, false)]);
+}
+
+fn vk_events(inputs: &[Input]) -> Vec<(u8, bool)> {
+    inputs.iter().map(|i| match i.as_keyboard().unwrap().e {
+        KeyboardInputEnum::VirtualKeyCode{code, ..} => (code.0 as u8, i.as_keyboard().unwrap().key_up),
+        other => panic!("expected a virtual key, got {:?}", other),
+    }).collect()
+}
+
+#[test]
+fn held_modifiers_are_released_and_restored() {
+    use winput_stuffer::send::{inputs_for_text_with_options, TextOptions};
+
+    let us = KeyboardLayout::us();
+    let options = TextOptions{release_held_modifiers: true, ..Default::default()};
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(&keys(&[(VK_LCONTROL, true), (VK_RSHIFT, true)]));
+
+    // Otherwise the held Ctrl swallows everything
+    let mut inputs = vec![];
+    inputs_for_text_with_state("hi", &us, &sim, &mut inputs);
+    let mut held = KeyboardSimulator::new(&us);
+    held.feed_all(&keys(&[(VK_LCONTROL, true), (VK_RSHIFT, true)]));
+    held.feed_all(&inputs);
+    assert_eq!(held.text(), "");
+
+    inputs.clear();
+    inputs_for_text_with_options("hi", &us, &sim, &options, &mut inputs);
+    assert_eq!(vk_events(&inputs)[..2], [(VK_RSHIFT, true), (VK_LCONTROL, true)]);
+    assert_eq!(vk_events(&inputs)[inputs.len() - 2..], [(VK_RSHIFT, false), (VK_LCONTROL, false)]);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), "hi");
+    assert!(sim.is_down(VK_LCONTROL) && sim.is_down(VK_RSHIFT));
+
+    // Nothing held, nothing extra
+    inputs.clear();
+    inputs_for_text_with_options("hi", &us, &KeyState::default(), &options, &mut inputs);
+    assert_eq!(inputs.len(), 4);
+}
+
+#[test]
+fn released_alt_is_masked_with_ctrl() {
+    use winput_stuffer::send::{inputs_for_text_with_options, TextOptions};
+
+    let us = KeyboardLayout::us();
+    let options = TextOptions{release_held_modifiers: true, ..Default::default()};
+    let mut inputs = vec![];
+    inputs_for_text_with_options("x", &us, &KeyState::default().with_down(VK_LMENU), &options, &mut inputs);
+    assert_eq!(vk_events(&inputs), vec![
+        (VK_LCONTROL, false), (VK_LCONTROL, true),
+        (VK_LMENU, true),
+        (VK_X, false), (VK_X, true),
+        (VK_LMENU, false),
+        (VK_LCONTROL, false), (VK_LCONTROL, true),
+    ]);
+}
+
+#[test]
+fn newlines_tabs_and_control_chars_follow_options() {
+    use winput_stuffer::send::{inputs_for_text_with_options, TextOptions, NewlinePolicy, TabPolicy, ControlCharPolicy};
+
+    let us = KeyboardLayout::us();
+    let planned = |text: &str, options: &TextOptions| {
+        let mut inputs = vec![];
+        inputs_for_text_with_options(text, &us, &KeyState::default(), options, &mut inputs);
+        inputs
+    };
+
+    // "\r\n", "\r" and "\n" are one Enter each
+    let defaults = TextOptions::default();
+    assert_eq!(vk_events(&planned("a\r\nb\rc\nd", &defaults)).iter().filter(|e| **e == (VK_RETURN, false)).count(), 3);
+    let crlf = TextOptions{collapse_crlf: false, ..Default::default()};
+    assert_eq!(typed(&us, &planned("a\r\nb", &crlf)), "a\r\rb");
+
+    let soft = TextOptions{newline: NewlinePolicy::ShiftEnter, ..Default::default()};
+    assert_eq!(vk_events(&planned("\n", &soft)), vec![
+        (VK_SHIFT, false), (VK_RETURN, false), (VK_RETURN, true), (VK_SHIFT, true),
+    ]);
+
+    assert_eq!(vk_events(&planned("\t", &defaults)), vec![(VK_TAB, false), (VK_TAB, true)]);
+    let spaces = TextOptions{tab: TabPolicy::Spaces(4), ..Default::default()};
+    assert_eq!(typed(&us, &planned("a\tb", &spaces)), "a    b");
+    let unicode = TextOptions{tab: TabPolicy::Unicode, ..Default::default()};
+    let inputs = planned("\t", &unicode);
+    assert!(matches!(inputs[0].as_keyboard().unwrap().e, KeyboardInputEnum::UnicodeCodeUnit(9)));
+    assert_eq!(typed(&us, &inputs), "\t");
+
+    let drop = TextOptions{control_chars: ControlCharPolicy::Drop, ..Default::default()};
+    assert_eq!(typed(&us, &planned("a\x07\x1b\u{7f}b\n", &drop)), "ab\r");
+    assert_eq!(typed(&us, &planned("\x07", &defaults)), "\x07");
+}
+
+#[test]
+fn alt_numpad_fallback() {
+    use winput_stuffer::layout::{CP437, CP1252};
+    use winput_stuffer::send::{inputs_for_text_with_options, TextOptions, CharFallback};
+
+    let us = KeyboardLayout::us();
+    let num_lock = KeyState::default().with_toggled(VK_NUMLOCK);
+    let decimal = TextOptions{fallback: CharFallback::AltNumpad{oem: &CP437, ansi: &CP1252}, ..Default::default()};
+    let mut inputs = vec![];
+    inputs_for_text_with_options("é", &us, &num_lock, &decimal, &mut inputs);
+    assert_eq!(vk_events(&inputs), vec![
+        (VK_LMENU, false),
+        (VK_NUMPAD0, false), (VK_NUMPAD0, true),
+        (VK_NUMPAD2, false), (VK_NUMPAD2, true),
+        (VK_NUMPAD3, false), (VK_NUMPAD3, true),
+        (VK_NUMPAD3, false), (VK_NUMPAD3, true),
+        (VK_LMENU, true),
+    ]);
+
+    // ╬ is only in the OEM code page, and the emoji in neither
+    let text = "a é€╬ 😀";
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.set_toggled(VK_NUMLOCK, true);
+    inputs.clear();
+    inputs_for_text_with_options(text, &us, &sim, &decimal, &mut inputs);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), text);
+
+    // Num Lock is turned on for each code and back off
+    let mut sim = KeyboardSimulator::new(&us);
+    inputs.clear();
+    inputs_for_text_with_options(text, &us, &sim, &decimal, &mut inputs);
+    assert_eq!(vk_events(&inputs[4..6]), vec![(VK_NUMLOCK, false), (VK_NUMLOCK, true)]);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), text);
+    assert!(!sim.is_toggled(VK_NUMLOCK));
+
+    let hex = TextOptions{fallback: CharFallback::AltNumpadHex, ..Default::default()};
+    inputs.clear();
+    inputs_for_text_with_options("ሴ", &us, &num_lock, &hex, &mut inputs);
+    assert_eq!(vk_events(&inputs)[..3], [(VK_LMENU, false), (VK_ADD, false), (VK_ADD, true)]);
+    let text = "é€ሴ߿ 😀";
+    let mut sim = KeyboardSimulator::new(&us);
+    inputs.clear();
+    inputs_for_text_with_options(text, &us, &sim, &hex, &mut inputs);
+    sim.feed_all(&inputs);
+    assert_eq!(sim.text(), text);
+}
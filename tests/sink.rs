@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+
+use winput_stuffer::{Error, KeyboardLayout, Result};
+use winput_stuffer::input::Input;
+use winput_stuffer::key_state::KeyState;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::send::{key_event, send_key_layout, send_key_with_state, send_text_with_state, TextOptions};
+use winput_stuffer::simulate::KeyboardSimulator;
+use winput_stuffer::sink::{send_all, InputSink, RecordingSink, SendReport};
+
+/// Accepts at most the next limit in `limits` of each batch, and everything once they run out. A limit of 0 fails like SendInput does.
+struct FlakySink {
+    limits: VecDeque<usize>,
+    sink: RecordingSink,
+}
+
+impl FlakySink {
+    fn new(limits: &[usize]) -> Self {
+        Self{limits: limits.iter().copied().collect(), sink: RecordingSink::new()}
+    }
+}
+
+impl InputSink for FlakySink {
+    fn send(&mut self, inputs: &[Input]) -> Result<u32> {
+        let limit = self.limits.pop_front().unwrap_or(usize::MAX).min(inputs.len());
+        if limit == 0 {
+            return Err(Error::BlockedByUipi);
+        }
+        self.sink.send(&inputs[..limit])
+    }
+}
+
+fn key(vk: u8, down: bool) -> Input {
+    Input::from_keyboard(&key_event(vk, down, None))
+}
+
+fn partial(res: Result<SendReport>) -> SendReport {
+    match res {
+        Err(Error::PartialSend(report)) => report,
+        other => panic!("expected a partial send, got {:?}", other),
+    }
+}
+
+#[test]
+fn keys_left_down_are_released() {
+    let us = KeyboardLayout::us();
+    // Shift down, A down, A up, Shift up, but only the first two get through
+    let mut sink = FlakySink::new(&[2]);
+    let report = partial(send_text_with_state("A", &us, &KeyState::default(), &TextOptions::default(), &mut sink));
+    assert!(!report.is_complete());
+    assert_eq!(report.accepted, 2);
+    assert_eq!(report.total, 4);
+    assert_eq!(report.last_accepted(), Some(1));
+    assert_eq!(report.retries, 0);
+    assert_eq!(report.released, vec![key(VK_A, false), key(VK_SHIFT, false)]);
+    assert!(report.stuck.is_empty());
+
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(sink.sink.inputs());
+    assert_eq!(sim.text(), "A");
+    assert!(!sim.is_down(VK_SHIFT) && !sim.is_down(VK_A));
+}
+
+#[test]
+fn the_rest_is_retried() {
+    let us = KeyboardLayout::us();
+    let options = TextOptions{retries: 2, ..Default::default()};
+    let mut sink = FlakySink::new(&[3, 0]);
+    let report = send_text_with_state("Hi", &us, &KeyState::default(), &options, &mut sink).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.retries, 2);
+    assert!(report.released.is_empty());
+    let mut sim = KeyboardSimulator::new(&us);
+    sim.feed_all(sink.sink.inputs());
+    assert_eq!(sim.text(), "Hi");
+
+    // Running out of retries
+    let mut sink = FlakySink::new(&[3, 0, 0]);
+    let options = TextOptions{retries: 1, ..Default::default()};
+    let report = partial(send_text_with_state("Hi", &us, &KeyState::default(), &options, &mut sink));
+    assert_eq!((report.accepted, report.retries), (3, 1));
+    assert_eq!(report.stuck, vec![key(VK_SHIFT, false)]);
+}
+
+#[test]
+fn nothing_accepted_is_an_error() {
+    let err = send_all(&[key(VK_A, true), key(VK_A, false)], 3, &mut FlakySink::new(&[0])).unwrap_err();
+    assert!(matches!(err, Error::BlockedByUipi));
+    assert!(send_all(&[], 0, &mut FlakySink::new(&[0])).unwrap().is_complete());
+}
+
+#[test]
+fn partial_key_sends_dont_panic() {
+    let us = KeyboardLayout::us();
+    // Num Lock tap, keypad 0 down, Num Lock tap
+    let mut sink = FlakySink::new(&[3]);
+    let report = partial(send_key_with_state("kp_0", true, &us, &KeyState::default(), &mut sink));
+    assert_eq!((report.accepted, report.total), (3, 5));
+    assert_eq!(report.released, vec![key(VK_NUMPAD0, false)]);
+
+    assert!(send_key_layout("shift_l", true, &us, &mut FlakySink::new(&[0])).is_err());
+}
+
+#[test]
+fn unknown_key_names_are_errors() {
+    let us = KeyboardLayout::us();
+    let mut sink = RecordingSink::new();
+    match send_key_layout("no_such_key", true, &us, &mut sink) {
+        Err(Error::UnknownKeyName(name)) => assert_eq!(name, "no_such_key"),
+        other => panic!("expected an unknown key name, got {:?}", other),
+    }
+    assert!(sink.inputs().is_empty());
+}
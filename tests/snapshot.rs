@@ -0,0 +1,44 @@
+#![cfg(feature = "serde")]
+
+use winput_stuffer::layout::*;
+use winput_stuffer::layout::maps::*;
+use winput_stuffer::KeyboardLayout;
+
+#[test]
+fn json_roundtrip() {
+    let layout = KeyboardLayout::us();
+    let json = layout.to_json();
+    assert_eq!(KeyboardLayout::from_json(&json).unwrap(), layout);
+    // Maps are written in order, so capturing twice gives the same file
+    assert_eq!(KeyboardLayout::from_json(&json).unwrap().to_json(), json);
+}
+
+#[test]
+fn toml_roundtrip() {
+    let layout = KeyboardLayout::us();
+    let toml = layout.to_toml();
+    let loaded = KeyboardLayout::from_toml(&toml).unwrap();
+    assert_eq!(loaded, layout);
+    assert_eq!(loaded.char_to_vk_ss()[&'~'], (VK_OEM_3, SHIFT_STATE_SHIFT));
+    assert_eq!(loaded.ss_to_vks()[&(SHIFT_STATE_SHIFT | SHIFT_STATE_CTRL)], vec![VK_SHIFT, VK_CONTROL]);
+}
+
+#[test]
+fn save_and_load_pick_format_by_extension() {
+    let layout = KeyboardLayout::us();
+    let dir = std::env::temp_dir().join(format!("winput-stuffer-snapshot-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["us.json", "us.toml"] {
+        let path = dir.join(name);
+        layout.save(&path).unwrap();
+        assert_eq!(KeyboardLayout::load(&path).unwrap(), layout);
+    }
+    assert!(std::fs::read_to_string(dir.join("us.toml")).unwrap().starts_with("layout_id = "));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_snapshot_is_an_error() {
+    let err = KeyboardLayout::from_json(r#"{"layout_id": 1, "char_to_vk_ss": {"ab": [1, 0]}, "keyname_to_vk": {}, "ss_to_vks": {}}"#).unwrap_err();
+    assert!(matches!(err, winput_stuffer::Error::InvalidData(_)), "{:?}", err);
+}
@@ -0,0 +1,108 @@
+#![cfg(target_os = "linux")]
+
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::Duration;
+
+use winput_stuffer::input::*;
+use winput_stuffer::send::send_text_with_msg_layout;
+use winput_stuffer::sink::InputSink;
+use winput_stuffer::uinput::UinputSink;
+use winput_stuffer::KeyboardLayout;
+
+const EV_KEY:u16 = 0x01;
+const EV_REL:u16 = 0x02;
+const EV_ABS:u16 = 0x03;
+
+/// Creating devices needs write access to /dev/uinput, which containers often don't have.
+fn make_sink() -> Option<UinputSink> {
+    match UinputSink::new() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            eprintln!("skipping, could not create uinput devices: {}", e);
+            None
+        }
+    }
+}
+
+fn open_node(nodes: std::io::Result<Vec<std::path::PathBuf>>) -> File {
+    // udev may take a moment to create the node
+    std::thread::sleep(Duration::from_millis(100));
+    let node = nodes.unwrap().into_iter().next().expect("device has no event node");
+    OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(node).unwrap()
+}
+
+/// Reads all pending events, minus the SYN_REPORTs, as (type, code, value)
+fn read_events(f: &mut File) -> Vec<(u16, u16, i32)> {
+    std::thread::sleep(Duration::from_millis(50));
+    let size = std::mem::size_of::<libc::input_event>();
+    let mut buf = vec![0u8; size * 256];
+    let mut res = vec![];
+    while let Ok(n) = f.read(&mut buf) {
+        if n == 0 { break }
+        for chunk in buf[..n].chunks_exact(size) {
+            let ev:libc::input_event = unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const _) };
+            if ev.type_ != 0 {
+                res.push((ev.type_, ev.code, ev.value));
+            }
+        }
+    }
+    res
+}
+
+#[test]
+fn text_becomes_evdev_keys() {
+    let Some(mut sink) = make_sink() else { return };
+    let mut node = open_node(sink.keyboard_event_nodes());
+
+    send_text_with_msg_layout("Hi", None, &KeyboardLayout::us(), &mut sink).unwrap();
+
+    assert_eq!(read_events(&mut node), vec![
+        (EV_KEY, 42, 1), // KEY_LEFTSHIFT
+        (EV_KEY, 35, 1), // KEY_H
+        (EV_KEY, 35, 0),
+        (EV_KEY, 42, 0),
+        (EV_KEY, 23, 1), // KEY_I
+        (EV_KEY, 23, 0),
+    ]);
+}
+
+#[test]
+fn mouse_becomes_evdev_buttons_and_axes() {
+    let Some(mut sink) = make_sink() else { return };
+    let mut kb_node = open_node(sink.keyboard_event_nodes());
+    let mut pointer_node = open_node(sink.pointer_event_nodes());
+
+    let mouse = |e| Input::from_mouse(&MouseInput{e, msg: None, time: None});
+    sink.send(&[
+        mouse(MouseInputEnum::Move{m: MouseMovement::Relative{dx: 5, dy: -3}, coalesce: true}),
+        mouse(MouseInputEnum::Button{which: MouseButton::Right, button_up: false}),
+        mouse(MouseInputEnum::Button{which: MouseButton::Right, button_up: true}),
+        mouse(MouseInputEnum::Wheel{horizontal: false, amount: -240}),
+        mouse(MouseInputEnum::Wheel{horizontal: true, amount: 60}),
+        mouse(MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x: 100, y: 65535}, coalesce: true}),
+    ]).unwrap();
+
+    assert_eq!(read_events(&mut kb_node), vec![
+        (EV_REL, 0x00, 5),
+        (EV_REL, 0x01, -3),
+        (EV_KEY, 0x111, 1),
+        (EV_KEY, 0x111, 0),
+        (EV_REL, 0x08, -2),
+        (EV_REL, 0x0b, -240),
+        (EV_REL, 0x0c, 60),
+    ]);
+    assert_eq!(read_events(&mut pointer_node), vec![
+        (EV_ABS, 0x00, 100),
+        (EV_ABS, 0x01, 65535),
+    ]);
+}
+
+#[test]
+fn unicode_input_is_rejected() {
+    let Some(mut sink) = make_sink() else { return };
+    let k = KeyboardInput{e: KeyboardInputEnum::UnicodeCodeUnit(0x263a), key_up: false, msg: None, time: None};
+    let err = sink.send(&[Input::from_keyboard(&k)]).unwrap_err();
+    assert!(matches!(&err, winput_stuffer::Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput), "{:?}", err);
+}
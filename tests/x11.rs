@@ -0,0 +1,186 @@
+#![cfg(all(target_os = "linux", feature = "x11"))]
+
+//! These start a private Xvfb server, so they need the `Xvfb` binary on $PATH and are skipped without it.
+
+use std::os::raw::c_ulong;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use x11_dl::xlib::{self, Xlib};
+
+use winput_stuffer::input::*;
+use winput_stuffer::send::send_text_with_msg_layout;
+use winput_stuffer::sink::InputSink;
+use winput_stuffer::x11::X11Sink;
+use winput_stuffer::KeyboardLayout;
+
+const WIDTH:u32 = 800;
+const HEIGHT:u32 = 600;
+
+struct Xvfb {
+    child: Child,
+    display: String,
+}
+
+impl Xvfb {
+    fn start() -> Option<Self> {
+        // Tests run in parallel, so each one needs its own display number
+        for n in 0..50 {
+            let num = 90 + (std::process::id() as usize + n) % 400;
+            let socket = format!("/tmp/.X11-unix/X{}", num);
+            if std::path::Path::new(&socket).exists() {
+                continue;
+            }
+            let display = format!(":{}", num);
+            let child = match Command::new("Xvfb")
+                .args([&display, "-screen", "0", &format!("{}x{}x24", WIDTH, HEIGHT), "-nolisten", "tcp"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("skipping, could not start Xvfb: {}", e);
+                    return None;
+                },
+            };
+            let mut xvfb = Self{child, display};
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(10) {
+                if std::path::Path::new(&socket).exists() {
+                    return Some(xvfb);
+                }
+                if let Ok(Some(_)) = xvfb.child.try_wait() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            let _ = xvfb.child.kill();
+        }
+        eprintln!("skipping, Xvfb did not start");
+        None
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A focused window covering the whole screen, recording what it receives.
+struct Window {
+    xlib: Xlib,
+    display: *mut xlib::Display,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Key(c_ulong),
+    Button(u32),
+    Motion(i32, i32),
+}
+
+impl Window {
+    fn open(display_name: &str) -> Self {
+        let xlib = Xlib::open().unwrap();
+        let name = std::ffi::CString::new(display_name).unwrap();
+        unsafe {
+            let display = (xlib.XOpenDisplay)(name.as_ptr());
+            assert!(!display.is_null());
+            let root = (xlib.XDefaultRootWindow)(display);
+            let window = (xlib.XCreateSimpleWindow)(display, root, 0, 0, WIDTH, HEIGHT, 0, 0, 0);
+            (xlib.XSelectInput)(display, window, xlib::KeyPressMask | xlib::ButtonPressMask | xlib::PointerMotionMask | xlib::StructureNotifyMask);
+            (xlib.XMapWindow)(display, window);
+            let mut ev:xlib::XEvent = std::mem::zeroed();
+            loop {
+                (xlib.XNextEvent)(display, &mut ev);
+                if ev.get_type() == xlib::MapNotify { break }
+            }
+            (xlib.XSetInputFocus)(display, window, xlib::RevertToParent, xlib::CurrentTime);
+            (xlib.XSync)(display, xlib::False);
+            Self{xlib, display}
+        }
+    }
+
+    fn events(&self) -> Vec<Event> {
+        let mut res = vec![];
+        unsafe {
+            (self.xlib.XSync)(self.display, xlib::False);
+            while (self.xlib.XPending)(self.display) > 0 {
+                let mut ev:xlib::XEvent = std::mem::zeroed();
+                (self.xlib.XNextEvent)(self.display, &mut ev);
+                match ev.get_type() {
+                    xlib::KeyPress => {
+                        let mut keysym = 0;
+                        let mut buf = [0; 16];
+                        (self.xlib.XLookupString)(&mut ev.key, buf.as_mut_ptr(), buf.len() as i32, &mut keysym, std::ptr::null_mut());
+                        res.push(Event::Key(keysym));
+                    },
+                    xlib::ButtonPress => res.push(Event::Button(ev.button.button)),
+                    xlib::MotionNotify => res.push(Event::Motion(ev.motion.x_root, ev.motion.y_root)),
+                    xlib::MappingNotify => { (self.xlib.XRefreshKeyboardMapping)(&mut ev.mapping); },
+                    _ => (),
+                }
+            }
+        }
+        res
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+#[test]
+fn text_becomes_keysyms() {
+    let Some(xvfb) = Xvfb::start() else { return };
+    let window = Window::open(&xvfb.display);
+    let mut sink = X11Sink::open(Some(&xvfb.display)).unwrap();
+
+    send_text_with_msg_layout("Hi é☺😀", None, &KeyboardLayout::us(), &mut sink).unwrap();
+
+    assert_eq!(window.events(), vec![
+        Event::Key(0xffe1), // Shift_L
+        Event::Key('H' as c_ulong),
+        Event::Key('i' as c_ulong),
+        Event::Key(' ' as c_ulong),
+        // Not in the US layout, so these came through as unicode and were put on spare keycodes
+        Event::Key(0xe9),
+        Event::Key(0x0100_263a),
+        Event::Key(0x0101_f600),
+    ]);
+}
+
+#[test]
+fn mouse_becomes_buttons_and_motion() {
+    let Some(xvfb) = Xvfb::start() else { return };
+    let window = Window::open(&xvfb.display);
+    let mut sink = X11Sink::open(Some(&xvfb.display)).unwrap();
+    assert_eq!((sink.screen_rect().width, sink.screen_rect().height), (WIDTH, HEIGHT));
+
+    let mouse = |e| Input::from_mouse(&MouseInput{e, msg: None, time: None});
+    sink.send(&[
+        mouse(MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x: 65535, y: 65535}, coalesce: true}),
+        mouse(MouseInputEnum::Move{m: MouseMovement::AbsoluteVirtualDesktop{x: 32768, y: 0}, coalesce: true}),
+        mouse(MouseInputEnum::Move{m: MouseMovement::Relative{dx: -10, dy: 5}, coalesce: true}),
+        mouse(MouseInputEnum::Button{which: MouseButton::Right, button_up: false}),
+        mouse(MouseInputEnum::Button{which: MouseButton::Right, button_up: true}),
+        mouse(MouseInputEnum::Wheel{horizontal: false, amount: -240}),
+        mouse(MouseInputEnum::Wheel{horizontal: true, amount: 60}),
+        mouse(MouseInputEnum::Wheel{horizontal: true, amount: 60}),
+    ]).unwrap();
+
+    assert_eq!(window.events(), vec![
+        Event::Motion(799, 599),
+        Event::Motion(400, 0),
+        Event::Motion(390, 5),
+        Event::Button(3),
+        Event::Button(5),
+        Event::Button(5),
+        Event::Button(7),
+    ]);
+}
@@ -0,0 +1,65 @@
+use std::process::{Command, Output};
+
+/// Runs the binary with the bundled US layout, so the output doesn't depend on the machine
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_winput-xdotool"))
+        .args(["--layout", "00000409", "--dry-run"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn dry_run(args: &[&str]) -> String {
+    let output = run(args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn error(args: &[&str]) -> String {
+    let output = run(args);
+    assert_eq!(output.status.code(), Some(1));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn keys() {
+    assert_eq!(dry_run(&["key", "ctrl+alt+Delete"]), "send LCONTROL down, LMENU down, DELETE down, DELETE up, LMENU up, LCONTROL up\n");
+    assert_eq!(dry_run(&["key", "--delay", "50", "Return", "A"]), "send RETURN down, RETURN up\nsleep 50ms\nsend SHIFT down, A down, A up, SHIFT up\n");
+    assert_eq!(dry_run(&["key", "--delay", "0", "XF86AudioMute", "super+Tab", "Meta_L", "BackSpace"]),
+        "send VOLUME_MUTE down, VOLUME_MUTE up, LWIN down, TAB down, TAB up, LWIN up, LMENU down, LMENU up, BACK down, BACK up\n");
+    assert_eq!(dry_run(&["key", "--repeat", "2", "--repeat-delay", "100", "a"]), "send A down, A up\nsleep 112ms\nsend A down, A up\n");
+    assert_eq!(dry_run(&["keydown", "alt", "key", "Tab", "keyup", "alt"]), "send LMENU down, TAB down, TAB up, LMENU up\n");
+}
+
+#[test]
+fn typing() {
+    assert_eq!(dry_run(&["type", "Hi"]), "send SHIFT down, H down, H up, SHIFT up\nsleep 12ms\nsend I down, I up\n");
+    // Each argument is typed on its own, without anything in between
+    assert_eq!(dry_run(&["type", "--delay", "0", "a", "b", "sleep", "1.5"]), "send A down, A up, B down, B up\nsleep 1.5s\n");
+}
+
+#[test]
+fn mouse() {
+    assert_eq!(dry_run(&["mousemove_relative", "--", "-5", "10", "click", "3"]), "send move by -5,10, right button down, right button up\n");
+    assert_eq!(dry_run(&["click", "--repeat", "2", "--delay", "50", "1"]), "send left button down, left button up\nsleep 50ms\nsend left button down, left button up\n");
+    assert_eq!(dry_run(&["click", "5", "click", "6", "mousedown", "8", "mouseup", "8"]), "send wheel -120, horizontal wheel -120, x1 button down, x1 button up\n");
+}
+
+#[test]
+fn errors() {
+    assert_eq!(error(&["frobnicate"]), "winput-xdotool: unknown command \"frobnicate\"\n");
+    assert_eq!(error(&["key", "ctrl+nope"]), "winput-xdotool: Unknown key name \"nope\"\n");
+    assert_eq!(error(&["key", "ctrl+"]), "winput-xdotool: empty key name in \"ctrl+\"\n");
+    assert_eq!(error(&["key"]), "winput-xdotool: key needs a key sequence\n");
+    assert_eq!(error(&["click", "0"]), "winput-xdotool: unknown button \"0\", expected 1 to 9\n");
+    assert_eq!(error(&["type", "--window", "1", "a"]), "winput-xdotool: type --window isn't supported\n");
+    assert_eq!(error(&["key", "--bogus", "a"]), "winput-xdotool: unknown option --bogus for key\n");
+    assert_eq!(error(&["sleep", "soon"]), "winput-xdotool: expected seconds, not \"soon\"\n");
+    assert_eq!(error(&["mousemove", "5"]), "winput-xdotool: mousemove needs two numbers of pixels\n");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn mousemove_needs_the_monitors() {
+    assert_eq!(error(&["mousemove", "--screen", "1", "5", "5"]), "winput-xdotool: There is no way to list the monitors on this platform\n");
+}